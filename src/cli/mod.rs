@@ -0,0 +1,427 @@
+mod output;
+
+use std::{fmt::Display, io::IsTerminal, path::PathBuf, process::ExitCode, time::Duration};
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use color_eyre::{eyre::eyre, Result};
+use tokio::runtime::Runtime;
+
+use crate::{
+    config::{Config, DownloadPolicy},
+    credentials,
+    custom_widgets::popup::PopupItem,
+    dreamhack::{
+        auth::Session,
+        catalog::Catalog,
+        challenge::{
+            handle::{Challenge, FlagResult, PageInfo},
+            RequestChallengeList,
+        },
+        client::DreamhackClient,
+        error::DreamhackError,
+        options::*,
+        query::SearchQuery,
+        ToRequestString,
+    },
+    utils::downloader::{self, DownloadOptions, Downloaded, Progress},
+};
+use output::OutputFormat;
+
+/// How often `download` redraws its progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Dreamhack TUI client, runs the TUI when no subcommand is given
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Output of search, vm and submit (json and ndjson carry a `schema_version`)
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Search wargame challenges
+    Search(SearchArgs),
+    /// Download the files of a challenge
+    Download {
+        id: u64,
+        /// Directory to save the files in (default: current directory)
+        #[arg(short, long)]
+        workdir: Option<PathBuf>,
+        /// What to do when the challenge was downloaded before (default: `download_policy` of the config)
+        #[arg(long, value_enum)]
+        on_exists: Option<DownloadPolicy>,
+    },
+    /// Manage the VM of a challenge
+    Vm {
+        #[command(subcommand)]
+        command: VmCommand,
+    },
+    /// Submit a flag, exits with 1 unless the flag is accepted
+    Submit { id: u64, flag: String },
+    /// Mirror the whole challenge list into the local catalog, for `search --local`
+    Sync {
+        /// Walk every page instead of stopping at the first one without new challenges
+        #[arg(long)]
+        full: bool,
+    },
+    /// Store the email and password in the OS keyring and log in
+    Login,
+    /// Remove the stored email, password and session
+    Logout,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VmCommand {
+    /// Create the VM and wait until it accepts connections
+    Create { id: u64 },
+    /// Show the state and ports of the VM
+    Info { id: u64 },
+}
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Keywords and filters, e.g. "heap cat:pwn level:3..5 solvers:<200" (see the readme)
+    query: Option<String>,
+    /// all, pwnable, reversing, web, crypto, misc, ... (any category of Dreamhack)
+    #[arg(short, long)]
+    category: Option<Category>,
+    /// all, beginner, 1 ~ 10
+    #[arg(short, long, value_parser = parse_option::<Difficulty>)]
+    difficulty: Option<Difficulty>,
+    /// todo, all, attempted, solved
+    #[arg(short, long, value_parser = parse_option::<Status>)]
+    status: Option<Status>,
+    /// newist, most-solved, least-solved
+    #[arg(short, long, value_parser = parse_option::<Orderings>)]
+    order: Option<Orderings>,
+    /// all, official, community
+    #[arg(long = "type", value_parser = parse_option::<ChallengeType>)]
+    type_value: Option<ChallengeType>,
+    /// all, beginner
+    #[arg(long, value_parser = parse_option::<Scope>)]
+    scope: Option<Scope>,
+    #[arg(short, long, default_value_t = 1)]
+    page: u64,
+    #[arg(long)]
+    page_size: Option<u64>,
+    /// Only use the cached results of earlier searches (also `offline` in the config)
+    #[arg(long)]
+    offline: bool,
+    /// Search the catalog of `creamhack sync` instead of the API
+    #[arg(long, conflicts_with_all = ["page", "page_size"])]
+    local: bool,
+}
+
+/// Accepts either the name shown in the TUI or the value sent to the API
+/// (e.g. "Most Solved", "most-solved" or "-cnt_solvers").
+fn parse_option<T>(value: &str) -> std::result::Result<T, String>
+where
+    T: PopupItem + Display + ToRequestString,
+{
+    let normalize = |s: &str| s.to_lowercase().replace([' ', '-', '_'], "");
+    let value = normalize(value);
+
+    T::variants()
+        .into_iter()
+        .find(|item| {
+            normalize(&item.to_string()) == value
+                || (!item.to_request_string().is_empty()
+                    && normalize(&item.to_request_string()) == value)
+        })
+        .ok_or_else(|| {
+            let names = T::variants()
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<String>>();
+            format!("expected one of: {}", names.join(", "))
+        })
+}
+
+/// Runs a subcommand, the exit code tells scripts whether a submitted flag was accepted.
+pub fn run(command: Command, format: OutputFormat, config: Config) -> Result<ExitCode> {
+    let runtime = Runtime::new()?;
+    let client = DreamhackClient::new(config.api_base_url.as_deref());
+
+    runtime.block_on(async {
+        let result = match command {
+            Command::Search(args) => search(&client, &config, format, args).await,
+            Command::Download {
+                id,
+                workdir,
+                on_exists,
+            } => download(&client, &config, id, workdir, on_exists).await,
+            Command::Vm {
+                command: VmCommand::Create { id },
+            } => vm_create(&client, format, id).await,
+            Command::Vm {
+                command: VmCommand::Info { id },
+            } => vm_info(&client, format, id).await,
+            Command::Submit { id, flag } => return submit(&client, format, id, &flag).await,
+            Command::Sync { full } => sync(&client, full).await,
+            Command::Login => login(&client).await,
+            Command::Logout => logout(),
+        };
+        result.map(|()| ExitCode::SUCCESS)
+    })
+}
+
+async fn search(
+    client: &DreamhackClient,
+    config: &Config,
+    format: OutputFormat,
+    args: SearchArgs,
+) -> Result<()> {
+    let query = SearchQuery::parse(args.query.as_deref().unwrap_or_default())?;
+    let mut request = RequestChallengeList::new();
+    request.set_page(args.page);
+    query.apply(&mut request);
+    if let Some(category) = args.category.clone() {
+        request.set_category(category);
+    }
+    if let Some(difficulty) = args.difficulty {
+        request.set_difficulty(difficulty);
+    }
+    if let Some(status) = args.status {
+        request.set_status(status);
+    }
+    if let Some(order) = args.order {
+        request.set_ordering(order);
+    }
+    if let Some(type_value) = args.type_value {
+        request.set_type(type_value);
+    }
+    if let Some(scope) = args.scope {
+        request.set_scope(scope);
+    }
+    if let Some(page_size) = args.page_size {
+        request.set_page_size(page_size);
+    }
+
+    let (mut challenges, page_info, cached_at) =
+        fetch_challenges(client, config, &request, &args).await?;
+    // Filters the API can not apply
+    challenges.retain(|challenge| query.matches(challenge));
+    output::print_search(format, &challenges, &page_info, cached_at)
+}
+
+/// Results of the catalog, the cache or the API, with the fetch time of cached ones.
+async fn fetch_challenges(
+    client: &DreamhackClient,
+    config: &Config,
+    request: &RequestChallengeList,
+    args: &SearchArgs,
+) -> Result<(Vec<Challenge>, PageInfo, Option<DateTime<Utc>>)> {
+    if args.local {
+        let catalog =
+            Catalog::load().ok_or_else(|| eyre!("No catalog, run `creamhack sync` first"))?;
+        let (challenges, page_info) = catalog.search(request);
+        return Ok((challenges, page_info, None));
+    }
+
+    let logged_in = credentials::load_login().is_some();
+    if args.offline || config.offline {
+        let (challenges, page_info, fetched_at) = request
+            .load_cached(client, logged_in)
+            .ok_or(DreamhackError::NotCached)?;
+        return Ok((challenges, page_info, Some(fetched_at)));
+    }
+
+    // Logged in users get their solved/attempted flags, everyone else searches anonymously
+    let result = match logged_in {
+        true => {
+            load_session(client)
+                .await?
+                .retry_on_expired(client, |auth| async move {
+                    request.send_request(client, Some(&auth)).await
+                })
+                .await
+        }
+        false => request.send_request(client, None).await,
+    };
+
+    match result {
+        Ok((challenges, page_info)) => Ok((challenges, page_info, None)),
+        // Without a connection the last results of the same search are better than nothing
+        Err(DreamhackError::Network(e)) => match request.load_cached(client, logged_in) {
+            Some((challenges, page_info, fetched_at)) => {
+                eprintln!("{}, showing cached results", DreamhackError::Network(e));
+                Ok((challenges, page_info, Some(fetched_at)))
+            }
+            None => Err(DreamhackError::Network(e).into()),
+        },
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn download(
+    client: &DreamhackClient,
+    config: &Config,
+    id: u64,
+    workdir: Option<PathBuf>,
+    on_exists: Option<DownloadPolicy>,
+) -> Result<()> {
+    let workdir = match workdir {
+        Some(workdir) => workdir,
+        None => std::env::current_dir()?,
+    };
+
+    let challenge = Challenge::fetch(client, id).await?;
+    let progress = Progress::default();
+    let download = downloader::download_file(
+        client,
+        &challenge,
+        workdir.clone(),
+        DownloadOptions {
+            policy: on_exists.unwrap_or(config.download_policy),
+            ..DownloadOptions::from(config)
+        },
+        &progress,
+    );
+    tokio::pin!(download);
+
+    // Progress is redrawn in place, so only on a terminal
+    let show_progress = std::io::stderr().is_terminal();
+    let result = loop {
+        tokio::select! {
+            result = &mut download => break result,
+            _ = tokio::time::sleep(PROGRESS_INTERVAL), if show_progress => {
+                eprint!("\r\x1b[KDownloading {}", progress);
+            }
+        }
+    };
+    if show_progress {
+        eprint!("\r\x1b[K");
+    }
+
+    match result.map_err(|e| eyre!(e))? {
+        Downloaded::Saved(_, report) => {
+            let report = report.unwrap_or_default();
+            println!(
+                "Downloaded {} to {}",
+                challenge.get_info().get_title(),
+                workdir.display()
+            );
+            if !report.is_empty() {
+                eprintln!("Extracted with {}:", report);
+                for line in report.lines() {
+                    eprintln!("  {}", line);
+                }
+            }
+        }
+        Downloaded::Skipped(path) => println!(
+            "Already downloaded to {}, skipped (see --on-exists)",
+            path.display()
+        ),
+    }
+    Ok(())
+}
+
+async fn sync(client: &DreamhackClient, full: bool) -> Result<()> {
+    // Logged in, the catalog knows which challenges are solved
+    let session = match credentials::load_login() {
+        Some(_) => Some(load_session(client).await?),
+        None => None,
+    };
+
+    let mut catalog = Catalog::load().unwrap_or_default();
+    let report = catalog.sync(client, session.as_ref(), full).await?;
+    catalog.save().map_err(|e| eyre!(e))?;
+
+    eprintln!(
+        "{} sync: fetched {}, {} new, {} challenges in the catalog",
+        if report.full { "Full" } else { "Incremental" },
+        report.fetched,
+        report.added,
+        catalog.len()
+    );
+    Ok(())
+}
+
+/// Session from the keyring, logs in when there is no stored session.
+async fn load_session(client: &DreamhackClient) -> Result<Session> {
+    let (email, password) = credentials::load_login()
+        .ok_or_else(|| eyre!("Not logged in, run `creamhack login` first"))?;
+
+    let session = Session::new(&email, &password, credentials::load_session())
+        .with_login_hook(credentials::save_session);
+    if !session.is_logged_in() {
+        session.login(client).await?;
+    }
+    Ok(session)
+}
+
+async fn vm_create(client: &DreamhackClient, format: OutputFormat, id: u64) -> Result<()> {
+    let session = load_session(client).await?;
+    let challenge = Challenge::fetch(client, id).await?;
+
+    let vm_info = session
+        .retry_on_expired(client, |auth| {
+            let challenge = &challenge;
+            async move {
+                challenge.create_vm(client, &auth).await?;
+                challenge.wait_for_vm(client, &auth).await
+            }
+        })
+        .await?;
+
+    output::print_vm_info(format, id, &vm_info)
+}
+
+async fn vm_info(client: &DreamhackClient, format: OutputFormat, id: u64) -> Result<()> {
+    let session = load_session(client).await?;
+    let challenge = Challenge::fetch(client, id).await?;
+
+    let vm_info = session
+        .retry_on_expired(client, |auth| {
+            let challenge = &challenge;
+            async move { challenge.get_vm_info(client, &auth).await }
+        })
+        .await?;
+
+    output::print_vm_info(format, id, &vm_info)
+}
+
+async fn submit(
+    client: &DreamhackClient,
+    format: OutputFormat,
+    id: u64,
+    flag: &str,
+) -> Result<ExitCode> {
+    let session = load_session(client).await?;
+    let challenge = Challenge::fetch(client, id).await?;
+
+    let result = session
+        .retry_on_expired(client, |auth| {
+            let challenge = &challenge;
+            async move { challenge.submit_flag(client, &auth, flag).await }
+        })
+        .await?;
+
+    output::print_flag_result(format, id, result)?;
+    match result {
+        FlagResult::Correct | FlagResult::AlreadySolved => Ok(ExitCode::SUCCESS),
+        _ => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn login(client: &DreamhackClient) -> Result<()> {
+    let (email, password) = credentials::prompt_login();
+
+    let session = Session::new(&email, &password, None).with_login_hook(credentials::save_session);
+    session.login(client).await?;
+    credentials::save_login(&email, &password);
+
+    println!("Logged in with email: {}", email);
+    Ok(())
+}
+
+fn logout() -> Result<()> {
+    credentials::clear_login()?;
+    println!("Removed the stored login");
+    Ok(())
+}
@@ -0,0 +1,312 @@
+//! Stable JSON / NDJSON output of the headless commands.
+//!
+//! The structs here are decoupled from the Dreamhack API responses, so new
+//! fields on the server side do not change the output. Bump `SCHEMA_VERSION`
+//! when a field is removed or changes meaning; adding a field does not need a bump.
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::dreamhack::{
+    challenge::handle::{format_age, Challenge, FlagResult, PageInfo},
+    vm_info::MachineInfo,
+};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable
+    #[default]
+    Text,
+    /// One JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChallengeOutput<'a> {
+    id: u64,
+    title: &'a str,
+    /// 0 is unranked, `null` when Dreamhack reports an unknown level
+    level: Option<u64>,
+    author: &'a str,
+    tags: &'a [String],
+    repository: &'a str,
+    solved: bool,
+    attempted: bool,
+    needs_vm: bool,
+    beginner: bool,
+    official: bool,
+    solvers: u64,
+    votes: u64,
+    writeups: u64,
+    has_author_writeup: bool,
+    /// RFC 3339
+    public_at: Option<String>,
+    created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageOutput {
+    page: u64,
+    count: u32,
+    page_size: u32,
+    has_next: bool,
+    has_previous: bool,
+}
+
+/// `page` record of `--format ndjson`
+#[derive(Debug, Serialize)]
+pub struct CachedPageOutput {
+    #[serde(flatten)]
+    page: PageOutput,
+    /// RFC 3339, set when the results come from the offline cache
+    cached_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchOutput<'a> {
+    challenges: Vec<ChallengeOutput<'a>>,
+    page: PageOutput,
+    /// RFC 3339, set when the results come from the offline cache
+    cached_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortOutput {
+    protocol: String,
+    host: String,
+    external: u16,
+    internal: u16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VmOutput<'a> {
+    challenge_id: u64,
+    state: &'a str,
+    /// RFC 3339
+    starttime: Option<String>,
+    endtime: Option<String>,
+    remaining_seconds: Option<i64>,
+    ports: Vec<PortOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlagOutput {
+    challenge_id: u64,
+    /// correct, wrong, already_solved, rate_limited or not_logged_in
+    result: &'static str,
+    accepted: bool,
+}
+
+/// Top level object of `--format json`
+#[derive(Serialize)]
+struct Document<T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    data: T,
+}
+
+/// Line of `--format ndjson`, `type` tells which kind of object it is
+#[derive(Serialize)]
+struct Record<T> {
+    schema_version: u32,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(flatten)]
+    data: T,
+}
+
+impl<'a> From<&'a Challenge> for ChallengeOutput<'a> {
+    fn from(challenge: &'a Challenge) -> Self {
+        let info = challenge.get_info();
+        let metadata = challenge.get_metadata();
+        let flags = metadata.get_flags();
+        let stats = metadata.get_stats();
+
+        ChallengeOutput {
+            id: challenge.get_id(),
+            title: info.get_title(),
+            level: info.get_difficulty().into(),
+            author: info.get_author(),
+            tags: info.get_tags(),
+            repository: metadata.get_repository(),
+            solved: flags.is_completed(),
+            attempted: flags.is_attempted(),
+            needs_vm: flags.needs_vm(),
+            beginner: flags.is_beginner(),
+            official: flags.is_official(),
+            solvers: stats.solvers,
+            votes: stats.votes,
+            writeups: stats.writeups,
+            has_author_writeup: flags.has_author_writeup(),
+            public_at: metadata.get_public_at().map(|time| time.to_rfc3339()),
+            created_at: metadata.get_created_at().map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+impl From<&PageInfo> for PageOutput {
+    fn from(page_info: &PageInfo) -> Self {
+        PageOutput {
+            page: page_info.get_page_idx(),
+            count: page_info.get_count(),
+            page_size: page_info.get_page_size(),
+            has_next: page_info.has_next(),
+            has_previous: page_info.has_previous(),
+        }
+    }
+}
+
+impl<'a> VmOutput<'a> {
+    pub fn new(challenge_id: u64, vm_info: &'a MachineInfo) -> Self {
+        VmOutput {
+            challenge_id,
+            state: vm_info.get_state(),
+            starttime: vm_info.get_starttime().map(|time| time.to_rfc3339()),
+            endtime: vm_info.get_endtime().map(|time| time.to_rfc3339()),
+            remaining_seconds: vm_info
+                .get_remaining()
+                .map(|remaining| remaining.num_seconds()),
+            ports: vm_info
+                .get_network_info()
+                .into_iter()
+                .map(|info| PortOutput {
+                    protocol: info.protocol.to_string().to_lowercase(),
+                    host: info.host,
+                    external: info.external,
+                    internal: info.internal,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FlagOutput {
+    pub fn new(challenge_id: u64, result: FlagResult) -> Self {
+        let (result, accepted) = match result {
+            FlagResult::Correct => ("correct", true),
+            FlagResult::AlreadySolved => ("already_solved", true),
+            FlagResult::Wrong => ("wrong", false),
+            FlagResult::RateLimited => ("rate_limited", false),
+            FlagResult::NotLoggedIn => ("not_logged_in", false),
+        };
+
+        FlagOutput {
+            challenge_id,
+            result,
+            accepted,
+        }
+    }
+}
+
+/// `cached_at` is the fetch time when the results come from the offline cache.
+pub fn print_search(
+    format: OutputFormat,
+    challenges: &[Challenge],
+    page_info: &PageInfo,
+    cached_at: Option<DateTime<Utc>>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for challenge in challenges.iter() {
+                let info = challenge.get_info();
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    challenge.get_id(),
+                    info.get_difficulty(),
+                    info.get_title(),
+                    info.get_author()
+                );
+            }
+            eprintln!(
+                "page {} ({} challenges)",
+                page_info.get_page_idx(),
+                page_info.get_count()
+            );
+            if let Some(cached_at) = cached_at {
+                eprintln!(
+                    "stale since {} ({} ago)",
+                    cached_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    format_age(Utc::now() - cached_at)
+                );
+            }
+        }
+        OutputFormat::Json => print_document(SearchOutput {
+            challenges: challenges.iter().map(ChallengeOutput::from).collect(),
+            page: page_info.into(),
+            cached_at: cached_at.map(|time| time.to_rfc3339()),
+        })?,
+        OutputFormat::Ndjson => {
+            for challenge in challenges.iter() {
+                print_record("challenge", ChallengeOutput::from(challenge))?;
+            }
+            print_record(
+                "page",
+                CachedPageOutput {
+                    page: page_info.into(),
+                    cached_at: cached_at.map(|time| time.to_rfc3339()),
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub fn print_vm_info(format: OutputFormat, challenge_id: u64, vm_info: &MachineInfo) -> Result<()> {
+    match format {
+        OutputFormat::Text => {
+            println!("state: {}", vm_info.get_state());
+            if let Some(remaining) = vm_info.get_remaining() {
+                println!("remaining: {}s", remaining.num_seconds());
+            }
+            for info in vm_info.get_network_info() {
+                println!(
+                    "{} {} -> {}",
+                    info.protocol,
+                    info.get_uri_pwn(),
+                    info.internal
+                );
+            }
+        }
+        OutputFormat::Json => print_document(VmOutput::new(challenge_id, vm_info))?,
+        OutputFormat::Ndjson => print_record("vm", VmOutput::new(challenge_id, vm_info))?,
+    }
+    Ok(())
+}
+
+pub fn print_flag_result(
+    format: OutputFormat,
+    challenge_id: u64,
+    result: FlagResult,
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => println!("{}", result),
+        OutputFormat::Json => print_document(FlagOutput::new(challenge_id, result))?,
+        OutputFormat::Ndjson => print_record("flag", FlagOutput::new(challenge_id, result))?,
+    }
+    Ok(())
+}
+
+fn print_document<T: Serialize>(data: T) -> Result<()> {
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        data,
+    };
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+fn print_record<T: Serialize>(kind: &'static str, data: T) -> Result<()> {
+    let record = Record {
+        schema_version: SCHEMA_VERSION,
+        kind,
+        data,
+    };
+    println!("{}", serde_json::to_string(&record)?);
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use anyhow::Context;
+use clap::ValueEnum;
+use ratatui::style::{Color, Style};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -7,9 +9,120 @@ pub struct Config {
     pub extract_chall_file: bool,
     pub keep_chall_file: bool,
     pub experimental_features: bool,
+    /// Dreamhack API base URL, overridden by `CREAMHACK_API_URL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+    /// Extra columns of the challenge list, e.g. `["solvers", "votes", "age", "writeup"]`
+    #[serde(default)]
+    pub list_columns: Vec<ListColumn>,
+    /// Initial layout of the challenge list, toggled with `t`
+    #[serde(default)]
+    pub list_view: ListView,
+    /// `no_color` drops the colours of badges and rows, so does the `NO_COLOR` env variable
+    #[serde(default)]
+    pub theme: Theme,
+    /// Serve searches from the on-disk cache only, toggled with Ctrl+O in the TUI
+    #[serde(default)]
+    pub offline: bool,
+    /// Search the catalog of `creamhack sync` instead of the API, toggled with Ctrl+L
+    #[serde(default)]
+    pub local_search: bool,
+    /// Search while typing, once the input pauses, toggled with Ctrl+T
+    #[serde(default)]
+    pub live_search: bool,
+    /// What downloading a challenge again does with the earlier download
+    #[serde(default)]
+    pub download_policy: DownloadPolicy,
+    /// Archives inside the challenge archive are extracted too, this many levels deep (0 = off)
+    #[serde(default)]
+    pub extract_nested_depth: u32,
+    /// Downloads in the TUI are not extracted until files are picked in the archive preview
+    #[serde(default)]
+    pub preview_archive: bool,
+    /// Guards against zip bombs, any archive going past them is not extracted further
+    #[serde(default)]
+    pub extract_limits: ExtractLimits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractLimits {
+    /// Bytes written for one download, nested archives included
+    pub max_total_size: u64,
+    /// Files, directories and links of one download, nested archives included
+    pub max_entries: u64,
+    /// Most an archive may expand to, as a multiple of its own size
+    pub max_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        ExtractLimits {
+            max_total_size: 4 << 30,
+            max_entries: 100_000,
+            max_ratio: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadPolicy {
+    /// Keep the earlier download and do nothing
+    #[default]
+    Skip,
+    /// Replace the archive and extract over the earlier files
+    Overwrite,
+    /// Save next to the earlier download as `<repository>-1.zip`, `<repository>-2.zip`, ...
+    Versioned,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Default,
+    NoColor,
+}
+
+impl Theme {
+    /// `NO_COLOR` (https://no-color.org) takes precedence over the config.
+    pub fn resolve(self) -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Theme::NoColor,
+            _ => self,
+        }
+    }
+
+    pub fn fg(&self, color: Color) -> Style {
+        match self {
+            Theme::Default => Style::default().fg(color),
+            Theme::NoColor => Style::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListView {
+    /// Four lines per challenge
+    #[default]
+    List,
+    /// One row per challenge
+    Table,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListColumn {
+    Solvers,
+    Votes,
+    Age,
+    Writeup,
 }
 
 // experimental-features
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Colors {}
 
@@ -41,6 +154,17 @@ impl Config {
                     extract_chall_file: true,
                     keep_chall_file: true,
                     experimental_features: false,
+                    api_base_url: None,
+                    list_columns: Vec::new(),
+                    list_view: ListView::default(),
+                    theme: Theme::default(),
+                    offline: false,
+                    local_search: false,
+                    live_search: false,
+                    download_policy: DownloadPolicy::default(),
+                    extract_nested_depth: 0,
+                    preview_archive: false,
+                    extract_limits: ExtractLimits::default(),
                 };
 
                 config.validate_config(); // Ensure `keep_chall_file` is valid
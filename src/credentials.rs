@@ -0,0 +1,93 @@
+use dialoguer::Input;
+use keyring::Entry;
+use rpassword::prompt_password;
+
+use crate::dreamhack::auth::Auth;
+
+const SERVICE: &str = "DreamhackService";
+const EMAIL_USER: &str = "dreamhack_email";
+const PASSWORD_USER: &str = "dreamhack_password";
+const SESSION_USER: &str = "dreamhack_session";
+
+pub fn email_entry() -> keyring::Result<Entry> {
+    Entry::new(SERVICE, EMAIL_USER)
+}
+
+pub fn password_entry() -> keyring::Result<Entry> {
+    Entry::new(SERVICE, PASSWORD_USER)
+}
+
+fn session_entry() -> keyring::Result<Entry> {
+    Entry::new(SERVICE, SESSION_USER)
+}
+
+/// Stored email and password, if both exist.
+pub fn load_login() -> Option<(String, String)> {
+    let email = email_entry().ok()?.get_secret().ok()?;
+    let password = password_entry().ok()?.get_password().ok()?;
+
+    Some((String::from_utf8_lossy(&email).into_owned(), password))
+}
+
+/// Asks for the email and password on the terminal.
+pub fn prompt_login() -> (String, String) {
+    let email: String = Input::new().with_prompt("Email").interact().unwrap();
+    let password = prompt_password("Password: ").unwrap();
+
+    (email, password)
+}
+
+pub fn save_login(email: &str, password: &str) {
+    // Create separate entries for email and password
+    match email_entry().and_then(|entry| entry.set_secret(email.as_bytes())) {
+        Ok(()) => println!("Successfully set email"),
+        #[allow(unused_variables)]
+        Err(err) => {
+            #[cfg(debug_assertions)]
+            log::error!("Error setting email: {err}")
+        }
+    }
+    match password_entry().and_then(|entry| entry.set_password(password)) {
+        Ok(()) => println!("Successfully set password"),
+        #[allow(unused_variables)]
+        Err(err) => {
+            #[cfg(debug_assertions)]
+            log::error!("Error setting password: {err}")
+        }
+    }
+}
+
+/// Removes the stored email, password and session.
+pub fn clear_login() -> keyring::Result<()> {
+    for entry in [email_entry()?, password_entry()?, session_entry()?] {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Session (`AuthKey`, `csrf_token` and `sessionid`) saved by the last login.
+pub fn load_session() -> Option<Auth> {
+    let session = session_entry().ok()?.get_password().ok()?;
+
+    let parsed = serde_json::from_str::<Auth>(&session);
+    #[cfg(debug_assertions)]
+    if let Err(e) = &parsed {
+        log::error!("Failed to parse stored session: {:?}", e);
+    }
+    parsed.ok()
+}
+
+pub fn save_session(auth: &Auth) {
+    let result = serde_json::to_string(auth)
+        .map_err(|e| keyring::Error::PlatformFailure(Box::new(e)))
+        .and_then(|session| session_entry()?.set_password(&session));
+
+    #[allow(unused_variables)]
+    if let Err(e) = result {
+        #[cfg(debug_assertions)]
+        log::error!("Error saving session: {e}");
+    }
+}
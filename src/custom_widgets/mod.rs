@@ -165,8 +165,14 @@ pub mod popup {
     }
 
     pub trait PopupOptions {
-        fn popup_options<T>(&self, area: Rect, frame: &mut Frame)
-        where
-            T: PopupItem + PartialEq;
+        /// With `marks`, each item gets a checkbox and more than one can be picked.
+        fn popup_options<T>(
+            &self,
+            area: Rect,
+            frame: &mut Frame,
+            items: &[T],
+            marks: Option<&[bool]>,
+        ) where
+            T: ToString;
     }
 }
@@ -0,0 +1,524 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+use crate::config::Theme;
+
+/// Renders a Dreamhack description (markdown mixed with HTML) into styled text.
+///
+/// Links and images are numbered in the text (`[1]`) and listed at the end.
+pub(crate) fn render_description(source: &str, theme: Theme) -> Text<'static> {
+    let mut renderer = Renderer::new(theme);
+
+    let parser = Parser::new_ext(
+        source,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    );
+    for event in parser {
+        renderer.handle_event(event);
+    }
+    renderer.finish()
+}
+
+struct Renderer {
+    theme: Theme,
+    lines: Vec<Line<'static>>,
+    /// Spans of the line being built
+    spans: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    /// `Some(next number)` for ordered lists
+    lists: Vec<Option<u64>>,
+    links: Vec<String>,
+    /// Numbers of the links/images that are still open
+    open_links: Vec<usize>,
+    in_code_block: bool,
+    quote_depth: usize,
+}
+
+impl Renderer {
+    fn new(theme: Theme) -> Self {
+        Renderer {
+            theme,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            styles: vec![Style::default()],
+            lists: Vec::new(),
+            links: Vec::new(),
+            open_links: Vec::new(),
+            in_code_block: false,
+            quote_depth: 0,
+        }
+    }
+
+    fn style(&self) -> Style {
+        *self.styles.last().unwrap_or(&Style::default())
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    fn code_style(&self) -> Style {
+        self.theme.fg(Color::LightYellow)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if self.spans.is_empty() && self.quote_depth > 0 {
+            self.spans.push(Span::styled(
+                "│ ".repeat(self.quote_depth),
+                self.theme.fg(Color::DarkGray),
+            ));
+        }
+        self.spans
+            .push(Span::styled(text.to_string(), self.style()));
+    }
+
+    /// Ends the current line, if it has any content.
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    /// Ends the current block with a blank line (never two in a row).
+    fn end_block(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn start_link(&mut self, url: &str) {
+        self.links.push(url.to_string());
+        self.open_links.push(self.links.len());
+        self.push_style(
+            self.theme
+                .fg(Color::LightCyan)
+                .add_modifier(Modifier::UNDERLINED),
+        );
+    }
+
+    fn end_link(&mut self) {
+        self.pop_style();
+        if let Some(number) = self.open_links.pop() {
+            self.spans.push(Span::styled(
+                format!(" [{}]", number),
+                self.theme.fg(Color::LightCyan),
+            ));
+        }
+    }
+
+    fn start_item(&mut self) {
+        self.flush();
+        let depth = self.lists.len().saturating_sub(1);
+        let bullet = match self.lists.last_mut() {
+            Some(Some(number)) => {
+                *number += 1;
+                format!("{}. ", *number - 1)
+            }
+            _ => "• ".to_string(),
+        };
+        self.spans
+            .push(Span::raw(format!("{}{}", "  ".repeat(depth), bullet)));
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start_tag(tag),
+            Event::End(tag) => self.end_tag(tag),
+            Event::Text(text) if self.in_code_block => {
+                for line in text.lines() {
+                    self.lines.push(Line::from(vec![
+                        Span::styled("▏ ", self.theme.fg(Color::DarkGray)),
+                        Span::styled(line.to_string(), self.code_style()),
+                    ]));
+                }
+            }
+            Event::Text(text) => self.push_text(&text),
+            Event::Code(code) => {
+                self.spans
+                    .push(Span::styled(code.to_string(), self.code_style()));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.handle_html(&html),
+            Event::SoftBreak => self.push_text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                self.lines
+                    .push(Line::styled("─".repeat(20), self.theme.fg(Color::DarkGray)));
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_text(if checked { "[x] " } else { "[ ] " });
+            }
+            _ => {}
+        }
+    }
+
+    fn start_tag(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.end_block();
+                let color = match level {
+                    HeadingLevel::H1 | HeadingLevel::H2 => Color::LightMagenta,
+                    _ => Color::LightBlue,
+                };
+                self.push_style(self.theme.fg(color).add_modifier(Modifier::BOLD));
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.end_block();
+                if let CodeBlockKind::Fenced(language) = kind {
+                    if !language.is_empty() {
+                        self.lines.push(Line::styled(
+                            language.to_string(),
+                            self.theme.fg(Color::DarkGray),
+                        ));
+                    }
+                }
+                self.in_code_block = true;
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => self.start_item(),
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } => self.start_link(&dest_url),
+            Tag::Image { dest_url, .. } => {
+                self.start_link(&dest_url);
+                self.push_text("image: ");
+            }
+            Tag::TableCell => self.push_text("| "),
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.pop_style();
+                self.end_block();
+            }
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                self.end_block();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.end_block();
+                }
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+            TagEnd::Link | TagEnd::Image => self.end_link(),
+            TagEnd::TableCell => self.push_text(" "),
+            TagEnd::TableHead | TagEnd::TableRow => self.flush(),
+            TagEnd::Table => self.end_block(),
+            _ => {}
+        }
+    }
+
+    /// Handles the HTML tags Dreamhack descriptions use, other tags are dropped.
+    fn handle_html(&mut self, html: &str) {
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                self.push_html_text(rest);
+                break;
+            };
+            self.push_html_text(&rest[..start]);
+
+            let Some(end) = rest[start..].find('>') else {
+                self.push_html_text(&rest[start..]);
+                break;
+            };
+            self.handle_html_tag(&rest[start + 1..start + end]);
+            rest = &rest[start + end + 1..];
+        }
+    }
+
+    fn push_html_text(&mut self, text: &str) {
+        // Newlines in HTML are just whitespace, runs of it collapse into one space
+        // also across the lines of an HTML block, which come one at a time
+        let after_space = self
+            .spans
+            .last()
+            .is_none_or(|span| span.content.ends_with(' '));
+        let mut collapsed = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if collapsed.is_empty() {
+            if !text.is_empty() && !after_space {
+                self.push_text(" ");
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace) && !after_space {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) {
+            collapsed.push(' ');
+        }
+        self.push_text(&decode_entities(&collapsed));
+    }
+
+    fn handle_html_tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/').trim();
+        let name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match (name.as_str(), closing) {
+            ("br", _) => self.flush(),
+            ("p" | "div", _) => self.end_block(),
+            ("a", false) => self.start_link(&html_attribute(tag, "href").unwrap_or_default()),
+            ("a", true) => self.end_link(),
+            ("img", _) => {
+                let alt = html_attribute(tag, "alt").unwrap_or_default();
+                self.start_link(&html_attribute(tag, "src").unwrap_or_default());
+                self.push_text(format!("image: {}", alt).trim_end());
+                self.end_link();
+            }
+            ("b" | "strong", false) => {
+                self.push_style(Style::default().add_modifier(Modifier::BOLD))
+            }
+            ("i" | "em", false) => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            ("code", false) => self.push_style(self.code_style()),
+            ("b" | "strong" | "i" | "em" | "code", true) => self.pop_style(),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.end_block();
+                self.push_style(self.theme.fg(Color::LightBlue).add_modifier(Modifier::BOLD));
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                self.pop_style();
+                self.end_block();
+            }
+            ("ul", false) => self.lists.push(None),
+            ("ol", false) => self.lists.push(Some(1)),
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.flush();
+            }
+            ("li", false) => self.start_item(),
+            ("li", true) => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Text<'static> {
+        self.flush();
+        while self.lines.last().is_some_and(|line| line.width() == 0) {
+            self.lines.pop();
+        }
+
+        if !self.links.is_empty() {
+            self.lines.push(Line::default());
+            self.lines.push(Line::styled(
+                "Links",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for (i, link) in self.links.iter().enumerate() {
+                self.lines.push(Line::from(vec![
+                    Span::styled(format!("[{}] ", i + 1), self.theme.fg(Color::LightCyan)),
+                    Span::raw(link.clone()),
+                ]));
+            }
+        }
+
+        Text::from(self.lines)
+    }
+}
+
+/// Value of `name="..."` (or single quoted) in a tag.
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let value = &tag[start..];
+
+    let value = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value.split_whitespace().next()?,
+    };
+    Some(decode_entities(value))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str) -> Text<'static> {
+        render_description(source, Theme::Default)
+    }
+
+    /// Content of each line, without styles.
+    fn lines(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn span<'a>(text: &'a Text<'static>, content: &str) -> &'a Span<'static> {
+        text.lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .find(|span| span.content == content)
+            .unwrap_or_else(|| panic!("no span {:?} in {:?}", content, lines(text)))
+    }
+
+    #[test]
+    fn empty_input_renders_nothing() {
+        assert!(render("").lines.is_empty());
+        assert!(render("  \n\n \n").lines.is_empty());
+        assert!(render("<p></p><br>").lines.is_empty());
+    }
+
+    #[test]
+    fn numbers_links_in_order() {
+        let text = render(
+            "See [the docs](https://a.io) and <a href=\"https://b.io\">this</a>.\n\n![logo](https://c.io/logo.png)",
+        );
+
+        assert_eq!(
+            lines(&text),
+            vec![
+                "See the docs [1] and this [2].",
+                "",
+                "image: logo [3]",
+                "",
+                "Links",
+                "[1] https://a.io",
+                "[2] https://b.io",
+                "[3] https://c.io/logo.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_html_images_with_alt_text() {
+        let text = render("<img src='https://a.io/x.png' alt=\"chart\"><img src=https://b.io>");
+
+        assert_eq!(
+            lines(&text),
+            vec![
+                "image: chart [1]image: [2]",
+                "",
+                "Links",
+                "[1] https://a.io/x.png",
+                "[2] https://b.io",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_links_close_innermost_first() {
+        let text = render("<a href=\"https://outer\">a <a href=\"https://inner\">b</a> c</a>");
+
+        assert_eq!(lines(&text)[0], "a b [2] c [1]");
+    }
+
+    #[test]
+    fn nested_styles_combine() {
+        let text = render("**bold _both_** plain");
+
+        assert_eq!(lines(&text), vec!["bold both plain"]);
+        assert_eq!(span(&text, "bold ").style.add_modifier, Modifier::BOLD);
+        assert_eq!(
+            span(&text, "both").style.add_modifier,
+            Modifier::BOLD | Modifier::ITALIC
+        );
+        assert_eq!(span(&text, " plain").style, Style::default());
+    }
+
+    #[test]
+    fn unclosed_markup_is_tolerated() {
+        // A stray closing tag is ignored, an unclosed one styles the rest
+        let text = render("<p></b>plain <b>bold</p>\n<p>still bold</p>");
+        assert_eq!(lines(&text), vec!["plain bold", "", "still bold"]);
+        assert_eq!(span(&text, "plain ").style, Style::default());
+        assert_eq!(span(&text, "still bold").style.add_modifier, Modifier::BOLD);
+
+        // A `<` without `>` is text
+        assert_eq!(lines(&render("<p>a < b")), vec!["a < b"]);
+
+        // An unclosed link is still listed
+        let text = render("<p><a href=\"https://a.io\">open");
+        assert_eq!(lines(&text), vec!["open", "", "Links", "[1] https://a.io"]);
+
+        assert_eq!(lines(&render("**not closed")), vec!["**not closed"]);
+    }
+
+    #[test]
+    fn separates_blocks_with_single_blank_lines() {
+        let text = render("<p>first\n   line</p>\n<p></p>\n<p>second<br>third</p>");
+
+        assert_eq!(lines(&text), vec!["first line", "", "second", "third"]);
+    }
+
+    #[test]
+    fn renders_lists_and_code() {
+        let text = render("1. one\n2. two\n   - nested\n\n```c\nint x;\n```");
+
+        assert_eq!(
+            lines(&text),
+            vec!["1. one", "2. two", "  • nested", "", "c", "▏ int x;"]
+        );
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            lines(&render("<p>&lt;flag&gt; &amp;&nbsp;&quot;x&quot;</p>")),
+            vec!["<flag> & \"x\""]
+        );
+    }
+
+    #[test]
+    fn no_color_theme_keeps_modifiers_only() {
+        let text = render_description("[link](https://a.io)", Theme::NoColor);
+
+        let link = span(&text, "link");
+        assert_eq!(link.style.fg, None);
+        assert_eq!(link.style.add_modifier, Modifier::UNDERLINED);
+    }
+}
@@ -0,0 +1,335 @@
+//! Queue of challenge downloads shown in the Downloads panel, and the preview
+//! of a downloaded archive.
+//!
+//! The queue only keeps the state of each item, `App` starts the downloads as
+//! tasks and reports back with `finish`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    custom_widgets::state_list::StateList,
+    dreamhack::challenge::handle::Challenge,
+    utils::{
+        downloader::{Downloaded, Progress},
+        file_extractor::{self, ArchiveEntry, ExtractReport, Selection},
+    },
+};
+
+pub(crate) struct QueueItem {
+    challenge: Challenge,
+    progress: Arc<Progress>,
+    status: QueueStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum QueueStatus {
+    Queued,
+    Running,
+    /// Not extracted yet while the report is `None`
+    Saved(PathBuf, Option<ExtractReport>),
+    /// Downloaded before, left alone by the `skip` policy
+    Skipped(PathBuf),
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Default)]
+pub(crate) struct DownloadQueue {
+    pub(crate) items: StateList<QueueItem>,
+}
+
+/// Entries of a downloaded archive, picked before extracting it.
+pub(crate) struct ArchivePreview {
+    challenge_id: u64,
+    archive: PathBuf,
+    pub(crate) entries: StateList<PreviewEntry>,
+    /// The single directory every entry is in
+    top_directory: Option<String>,
+    flatten: bool,
+}
+
+pub(crate) struct PreviewEntry {
+    entry: ArchiveEntry,
+    selected: bool,
+}
+
+impl QueueItem {
+    pub(crate) fn get_challenge(&self) -> &Challenge {
+        &self.challenge
+    }
+
+    pub(crate) fn get_progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    pub(crate) fn get_status(&self) -> &QueueStatus {
+        &self.status
+    }
+
+    /// A fresh counter, the download resumes from its partial file.
+    fn requeue(&mut self) {
+        self.progress = Arc::default();
+        self.status = QueueStatus::Queued;
+    }
+}
+
+impl QueueStatus {
+    pub(crate) fn is_active(&self) -> bool {
+        matches!(self, QueueStatus::Queued | QueueStatus::Running)
+    }
+
+    /// Failed and cancelled downloads can be retried.
+    pub(crate) fn is_retryable(&self) -> bool {
+        matches!(self, QueueStatus::Failed(_) | QueueStatus::Cancelled)
+    }
+}
+
+impl DownloadQueue {
+    /// Queues the challenges that are not queued or running already, returns how many.
+    ///
+    /// Finished challenges are queued again, the download policy decides what
+    /// happens to the earlier files.
+    pub(crate) fn push(&mut self, challenges: impl IntoIterator<Item = Challenge>) -> usize {
+        let mut added = 0;
+        for challenge in challenges {
+            let id = challenge.get_id();
+            if self.find(id).is_some_and(|item| item.status.is_active()) {
+                continue;
+            }
+
+            self.items
+                .items
+                .retain(|item| item.challenge.get_id() != id);
+            self.items.items.push(QueueItem {
+                challenge,
+                progress: Arc::default(),
+                status: QueueStatus::Queued,
+            });
+            added += 1;
+        }
+        added
+    }
+
+    /// Marks queued items as running until `limit` run at once, and returns them.
+    pub(crate) fn start_next(&mut self, limit: usize) -> Vec<(Challenge, Arc<Progress>)> {
+        let free = limit.saturating_sub(self.count(|status| *status == QueueStatus::Running));
+
+        self.items
+            .items
+            .iter_mut()
+            .filter(|item| item.status == QueueStatus::Queued)
+            .take(free)
+            .map(|item| {
+                item.status = QueueStatus::Running;
+                (item.challenge.clone(), item.progress.clone())
+            })
+            .collect()
+    }
+
+    pub(crate) fn finish(&mut self, id: u64, result: anyhow::Result<Downloaded>) {
+        if let Some(item) = self.find_mut(id) {
+            item.status = match result {
+                Ok(Downloaded::Saved(path, report)) => QueueStatus::Saved(path, report),
+                Ok(Downloaded::Skipped(path)) => QueueStatus::Skipped(path),
+                Err(e) => QueueStatus::Failed(format!("{:#}", e)),
+            };
+        }
+    }
+
+    /// Records the extraction of an archive downloaded without extracting it.
+    pub(crate) fn extracted(&mut self, id: u64, report: ExtractReport) {
+        if let Some(item) = self.find_mut(id) {
+            if let QueueStatus::Saved(_, extracted) = &mut item.status {
+                *extracted = Some(report);
+            }
+        }
+    }
+
+    /// Queues the selected item again, if it failed or was cancelled.
+    pub(crate) fn retry_selected(&mut self) {
+        if let Some(item) = self.get_selected_mut() {
+            if item.status.is_retryable() {
+                item.requeue();
+            }
+        }
+    }
+
+    /// Queues every failed item again.
+    pub(crate) fn retry_failed(&mut self) {
+        for item in self.items.items.iter_mut() {
+            if matches!(item.status, QueueStatus::Failed(_)) {
+                item.requeue();
+            }
+        }
+    }
+
+    /// Cancels the selected item, returns its id if it was running.
+    pub(crate) fn cancel_selected(&mut self) -> Option<u64> {
+        let item = self.get_selected_mut()?;
+        let running = item.status == QueueStatus::Running;
+        if item.status.is_active() {
+            item.status = QueueStatus::Cancelled;
+        }
+        running.then(|| item.challenge.get_id())
+    }
+
+    /// Drops everything that is neither queued nor running.
+    pub(crate) fn clear_finished(&mut self) {
+        self.items.items.retain(|item| item.status.is_active());
+        if self
+            .items
+            .state
+            .selected()
+            .is_some_and(|i| i >= self.items.items.len())
+        {
+            self.items.state.select(None);
+        }
+    }
+
+    /// The running download of a challenge.
+    pub(crate) fn get_running(&self, id: u64) -> Option<&QueueItem> {
+        self.find(id)
+            .filter(|item| item.status == QueueStatus::Running)
+    }
+
+    pub(crate) fn count(&self, filter: impl Fn(&QueueStatus) -> bool) -> usize {
+        self.items
+            .items
+            .iter()
+            .filter(|item| filter(&item.status))
+            .count()
+    }
+
+    fn find(&self, id: u64) -> Option<&QueueItem> {
+        self.items
+            .items
+            .iter()
+            .find(|item| item.challenge.get_id() == id)
+    }
+
+    fn find_mut(&mut self, id: u64) -> Option<&mut QueueItem> {
+        self.items
+            .items
+            .iter_mut()
+            .find(|item| item.challenge.get_id() == id)
+    }
+
+    pub(crate) fn get_selected(&self) -> Option<&QueueItem> {
+        let index = self.items.state.selected()?;
+        self.items.items.get(index)
+    }
+
+    fn get_selected_mut(&mut self) -> Option<&mut QueueItem> {
+        let index = self.items.state.selected()?;
+        self.items.items.get_mut(index)
+    }
+}
+
+impl PreviewEntry {
+    pub(crate) fn get_entry(&self) -> &ArchiveEntry {
+        &self.entry
+    }
+
+    pub(crate) fn is_selected(&self) -> bool {
+        self.selected
+    }
+}
+
+impl ArchivePreview {
+    /// Lists `archive`, with every entry selected.
+    pub(crate) fn open(challenge_id: u64, archive: PathBuf) -> anyhow::Result<Self> {
+        let entries = file_extractor::list_zip(&archive)?;
+        let top_directory =
+            file_extractor::top_directory(entries.iter().map(|entry| entry.name.as_str()));
+
+        Ok(ArchivePreview {
+            challenge_id,
+            archive,
+            entries: StateList {
+                items: entries
+                    .into_iter()
+                    .map(|entry| PreviewEntry {
+                        entry,
+                        selected: true,
+                    })
+                    .collect(),
+                state: Default::default(),
+            },
+            top_directory,
+            flatten: false,
+        })
+    }
+
+    pub(crate) fn get_challenge_id(&self) -> u64 {
+        self.challenge_id
+    }
+
+    pub(crate) fn get_archive(&self) -> &Path {
+        &self.archive
+    }
+
+    pub(crate) fn get_top_directory(&self) -> Option<&str> {
+        self.top_directory.as_deref()
+    }
+
+    pub(crate) fn is_flattened(&self) -> bool {
+        self.flatten
+    }
+
+    pub(crate) fn count_selected(&self) -> usize {
+        self.entries
+            .items
+            .iter()
+            .filter(|entry| entry.selected && !entry.entry.is_dir)
+            .count()
+    }
+
+    /// Toggles the selected entry, a directory together with everything in it.
+    pub(crate) fn toggle_selected(&mut self) {
+        let Some(index) = self.entries.state.selected() else {
+            return;
+        };
+        let Some(entry) = self.entries.items.get(index) else {
+            return;
+        };
+
+        // Directory names end with a slash, so they prefix their contents only
+        let selected = !entry.selected;
+        let name = entry.entry.name.clone();
+        let is_dir = entry.entry.is_dir;
+        for entry in self.entries.items.iter_mut() {
+            if entry.entry.name == name || (is_dir && entry.entry.name.starts_with(&name)) {
+                entry.selected = selected;
+            }
+        }
+    }
+
+    /// Selects every entry, or none when all are selected already.
+    pub(crate) fn toggle_all(&mut self) {
+        let selected = !self.entries.items.iter().all(|entry| entry.selected);
+        for entry in self.entries.items.iter_mut() {
+            entry.selected = selected;
+        }
+    }
+
+    /// Only with a single top-level directory to flatten.
+    pub(crate) fn toggle_flatten(&mut self) {
+        self.flatten = !self.flatten && self.top_directory.is_some();
+    }
+
+    pub(crate) fn selection(&self) -> Selection {
+        Selection {
+            entries: self
+                .entries
+                .items
+                .iter()
+                .filter(|entry| entry.selected)
+                .map(|entry| entry.entry.name.clone())
+                .collect(),
+            flatten: self.flatten,
+        }
+    }
+}
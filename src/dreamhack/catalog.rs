@@ -0,0 +1,554 @@
+//! Local mirror of the whole challenge list, searchable without the API.
+//!
+//! The catalog is a JSON file under the data directory. The full-text index is
+//! rebuilt in memory when the catalog is loaded.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    auth::Session,
+    challenge::{
+        compare_by_ordering,
+        handle::{Challenge, PageInfo},
+        ChallengeResponseData, RequestChallengeList,
+    },
+    client::DreamhackClient,
+    error::Result,
+    options::{Category, Difficulty, Orderings, Status},
+    ToRequestString,
+};
+
+/// Bump when the file format changes, older catalogs are synced again from scratch
+const CATALOG_VERSION: u32 = 1;
+
+const SYNC_PAGE_SIZE: u64 = 100;
+/// Pause between pages, so a sync does not hammer the API
+const SYNC_PAGE_DELAY: Duration = Duration::from_millis(200);
+
+/// Relevance of a match per field
+const TITLE_WEIGHT: u32 = 8;
+const TAG_WEIGHT: u32 = 4;
+const AUTHOR_WEIGHT: u32 = 2;
+const DESCRIPTION_WEIGHT: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct CatalogFile {
+    version: u32,
+    /// Unix timestamp in seconds
+    synced_at: i64,
+    /// Synced with a session, so the solved/attempted flags are the user's
+    logged_in: bool,
+    challenges: Vec<ChallengeResponseData>,
+}
+
+#[derive(Default)]
+pub struct Catalog {
+    synced_at: Option<DateTime<Utc>>,
+    logged_in: bool,
+    entries: BTreeMap<u64, ChallengeResponseData>,
+    /// `entries` converted for the UI, in the same order
+    challenges: Vec<Challenge>,
+    /// Token -> (index into `challenges`, weight of the best field it is in)
+    index: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+/// What a sync changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncReport {
+    pub fetched: usize,
+    pub added: usize,
+    pub full: bool,
+}
+
+impl Catalog {
+    pub fn get_path() -> Option<PathBuf> {
+        let mut path = dirs::data_dir()?;
+        path.push("creamhack/catalog.json");
+        Some(path)
+    }
+
+    /// `None` when there is no catalog yet, or it can not be read.
+    pub fn load() -> Option<Self> {
+        Self::load_from(&Self::get_path()?)
+    }
+
+    fn load_from(path: &Path) -> Option<Self> {
+        let file = fs::read_to_string(path).ok()?;
+        let file = match serde_json::from_str::<CatalogFile>(&file) {
+            Ok(file) if file.version == CATALOG_VERSION => file,
+            #[allow(unused_variables)]
+            result => {
+                #[cfg(debug_assertions)]
+                log::warn!("Ignoring catalog: {:?}", result.err());
+                return None;
+            }
+        };
+
+        let mut catalog = Catalog {
+            synced_at: DateTime::from_timestamp(file.synced_at, 0),
+            logged_in: file.logged_in,
+            entries: file
+                .challenges
+                .into_iter()
+                .map(|data| (data.get_id(), data))
+                .collect(),
+            ..Default::default()
+        };
+        catalog.reindex();
+        Some(catalog)
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a truncated catalog.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::get_path().ok_or(anyhow::anyhow!("No data directory"))?;
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let file = CatalogFile {
+            version: CATALOG_VERSION,
+            synced_at: self.synced_at.unwrap_or_else(Utc::now).timestamp(),
+            logged_in: self.logged_in,
+            challenges: self.entries.values().cloned().collect(),
+        };
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string(&file)?)?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get_challenges(&self) -> &[Challenge] {
+        &self.challenges
+    }
+
+    pub fn get_synced_at(&self) -> Option<DateTime<Utc>> {
+        self.synced_at
+    }
+
+    /// Walks the challenge list, newest first.
+    ///
+    /// An incremental sync stops at the first page without new challenges. A full
+    /// sync walks every page and drops challenges that are no longer listed, it is
+    /// forced when the catalog is empty or was synced with another login state.
+    pub async fn sync(
+        &mut self,
+        client: &DreamhackClient,
+        session: Option<&Session>,
+        full: bool,
+    ) -> Result<SyncReport> {
+        let logged_in = session.is_some();
+        let full = full || self.is_empty() || self.logged_in != logged_in;
+        let mut report = SyncReport {
+            full,
+            ..Default::default()
+        };
+
+        let mut entries = match full {
+            true => BTreeMap::new(),
+            false => self.entries.clone(),
+        };
+
+        let mut request = RequestChallengeList::new();
+        request.set_page_size(SYNC_PAGE_SIZE);
+        for page in 1.. {
+            request.set_page(page);
+            let request = &request;
+            let response = match session {
+                Some(session) => {
+                    session
+                        .retry_on_expired(client, |auth| async move {
+                            request.fetch_response(client, Some(&auth)).await
+                        })
+                        .await?
+                }
+                None => request.fetch_response(client, None).await?,
+            };
+
+            let has_next = response.has_next();
+            let mut added = 0;
+            for data in response.results {
+                report.fetched += 1;
+                if !self.entries.contains_key(&data.get_id()) {
+                    added += 1;
+                }
+                entries.insert(data.get_id(), data);
+            }
+            report.added += added;
+
+            #[cfg(debug_assertions)]
+            log::info!("Synced catalog page {} ({} new)", page, added);
+
+            if !has_next || (!full && added == 0) {
+                break;
+            }
+            tokio::time::sleep(SYNC_PAGE_DELAY).await;
+        }
+
+        self.entries = entries;
+        self.logged_in = logged_in;
+        self.synced_at = Some(Utc::now());
+        self.reindex();
+        Ok(report)
+    }
+
+    fn reindex(&mut self) {
+        self.challenges = self
+            .entries
+            .values()
+            .cloned()
+            .map(Challenge::from)
+            .collect();
+
+        let mut index: BTreeMap<String, HashMap<usize, u32>> = BTreeMap::new();
+        for (i, challenge) in self.challenges.iter().enumerate() {
+            let info = challenge.get_info();
+            let fields = [
+                (info.get_title().to_string(), TITLE_WEIGHT),
+                (info.get_tags().join(" "), TAG_WEIGHT),
+                (info.get_author().to_string(), AUTHOR_WEIGHT),
+                (strip_tags(info.get_description()), DESCRIPTION_WEIGHT),
+            ];
+
+            for (text, weight) in fields {
+                for token in tokenize(&text) {
+                    let score = index.entry(token).or_default().entry(i).or_default();
+                    *score = (*score).max(weight);
+                }
+            }
+        }
+
+        self.index = index
+            .into_iter()
+            .map(|(token, matches)| (token, matches.into_iter().collect()))
+            .collect();
+    }
+
+    /// Runs `request` over the catalog, with every result on a single page.
+    ///
+    /// Each word of the search has to prefix a word of the title, tags, author or
+    /// description. With the default ordering the most relevant challenges come first.
+    pub fn search(&self, request: &RequestChallengeList) -> (Vec<Challenge>, PageInfo) {
+        let tokens = tokenize(request.get_search());
+
+        let mut scores: Vec<Option<u32>> = vec![Some(0); self.challenges.len()];
+        for token in tokens.iter() {
+            let mut matched = vec![None; self.challenges.len()];
+            for (_, matches) in self
+                .index
+                .range(token.clone()..)
+                .take_while(|(key, _)| key.starts_with(token.as_str()))
+            {
+                for &(i, weight) in matches.iter() {
+                    matched[i] = Some(matched[i].unwrap_or(0).max(weight));
+                }
+            }
+            for (score, matched) in scores.iter_mut().zip(matched) {
+                *score = score.zip(matched).map(|(score, weight)| score + weight);
+            }
+        }
+
+        let mut results = self
+            .challenges
+            .iter()
+            .zip(scores)
+            .filter_map(|(challenge, score)| Some((challenge, score?)))
+            .filter(|(challenge, _)| matches_filters(challenge, request))
+            .collect::<Vec<(&Challenge, u32)>>();
+
+        let ordering = request.get_ordering();
+        match ordering {
+            Orderings::Newist => results.sort_by(|(a, a_score), (b, b_score)| {
+                b_score
+                    .cmp(a_score)
+                    .then_with(|| compare_by_ordering(a, b, ordering))
+            }),
+            _ => results.sort_by(|(a, _), (b, _)| compare_by_ordering(a, b, ordering)),
+        }
+
+        let challenges = results
+            .into_iter()
+            .map(|(challenge, _)| challenge.clone())
+            .collect::<Vec<Challenge>>();
+        let page_info = PageInfo::single_page(challenges.len());
+        (challenges, page_info)
+    }
+}
+
+/// Category, difficulty, status, type and scope options, applied like the API does.
+fn matches_filters(challenge: &Challenge, request: &RequestChallengeList) -> bool {
+    let info = challenge.get_info();
+    let flags = challenge.get_metadata().get_flags();
+
+    let category = match request.get_category() {
+        Category::All => true,
+        category => info
+            .get_tags()
+            .iter()
+            .any(|tag| tag.eq_ignore_ascii_case(&category.to_request_string())),
+    };
+    let difficulty = match request.get_difficulty() {
+        // Checked by `matches_flags`, as the beginner scope
+        Difficulty::All | Difficulty::Beginner => true,
+        difficulty => info.get_difficulty() == difficulty,
+    };
+    let status = match request.get_status() {
+        Status::All => true,
+        Status::ToDo => !flags.is_completed(),
+        Status::Attempted => flags.is_attempted() && !flags.is_completed(),
+        Status::Solved => flags.is_completed(),
+    };
+
+    category && difficulty && status && request.matches_flags(challenge)
+}
+
+/// Lowercase words, Hangul and other scripts included.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Drops HTML tags, so their names and attributes are not indexed.
+fn strip_tags(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                stripped.push(' ');
+            }
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(
+        id: u64,
+        title: &str,
+        tags: &[&str],
+        author: &str,
+        description: &str,
+    ) -> ChallengeResponseData {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "hit_count": { "hits": 0 },
+            "cnt_solvers": 10 * id,
+            "cnt_writeups": 0,
+            "cnt_vote": 0,
+            "cnt_questions": 0,
+            "cnt_comments": 0,
+            "is_completed": id.is_multiple_of(2),
+            "is_attempted": false,
+            "is_difficulty_voted": false,
+            "difficulty_display": "",
+            "partners": [],
+            "has_author_writeup": false,
+            "tags": tags,
+            "author": {
+                "id": 1,
+                "nickname": author,
+                "is_staff": false,
+                "wargame": { "rank": 0, "score": 0 },
+                "contributions": {
+                    "level": 1,
+                    "exp": 0,
+                    "total_exp": 0,
+                    "exp_needed": 0,
+                    "updated_at": "",
+                    "rank": 0,
+                    "totals": 0
+                }
+            },
+            "repository": "",
+            "title": title,
+            "description": description,
+            "public": "",
+            "public_expires": "",
+            "needs_vm": false,
+            "deployed": "",
+            "difficulty": 1,
+            "official": true,
+            "is_beginner": false,
+            "is_public": true,
+            "is_featured": false,
+            "created_at": format!("2024-01-0{}T00:00:00+09:00", id),
+            "public_at": format!("2024-01-0{}T00:00:00+09:00", id)
+        }))
+        .unwrap()
+    }
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog {
+            entries: [
+                challenge(
+                    1,
+                    "Heap Overflow",
+                    &["pwnable"],
+                    "alice",
+                    "<p>tcache poisoning</p>",
+                ),
+                challenge(
+                    2,
+                    "Cookie Monster",
+                    &["web"],
+                    "bob",
+                    "Steal the <b>admin</b> cookie from the heap",
+                ),
+                challenge(
+                    3,
+                    "Baby heap",
+                    &["pwnable"],
+                    "bob",
+                    "A first heap challenge",
+                ),
+                challenge(4, "힙 문제", &["reversing"], "carol", "설명"),
+            ]
+            .into_iter()
+            .map(|data| (data.get_id(), data))
+            .collect(),
+            ..Default::default()
+        };
+        catalog.reindex();
+        catalog
+    }
+
+    fn search(catalog: &Catalog, text: &str) -> Vec<u64> {
+        let mut request = RequestChallengeList::new();
+        request.set_search(text.to_string());
+        let (challenges, _) = catalog.search(&request);
+        challenges.iter().map(Challenge::get_id).collect()
+    }
+
+    #[test]
+    fn tokenizes_words() {
+        assert_eq!(
+            tokenize("Use-After-Free, in C++ 2.0!"),
+            vec!["use", "after", "free", "in", "c", "2", "0"]
+        );
+        assert_eq!(tokenize("힙 Overflow"), vec!["힙", "overflow"]);
+        assert!(tokenize("  -- ").is_empty());
+    }
+
+    #[test]
+    fn strips_html_tags() {
+        assert_eq!(strip_tags("<p class=\"x\">a<b>b</b></p>"), " a b  ");
+        assert!(tokenize(&strip_tags("<div class=\"title\">")).is_empty());
+    }
+
+    #[test]
+    fn matches_any_field_by_prefix() {
+        let catalog = catalog();
+
+        assert_eq!(search(&catalog, "overfl"), vec![1]);
+        assert_eq!(search(&catalog, "alice"), vec![1]);
+        assert_eq!(search(&catalog, "tcache"), vec![1]);
+        assert_eq!(search(&catalog, "힙"), vec![4]);
+        // Tag names of the description are not indexed
+        assert_eq!(search(&catalog, "p"), vec![3, 1]);
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let catalog = catalog();
+
+        // `heap` is in 1, 2 and 3, `bob` wrote 2 and 3
+        assert_eq!(search(&catalog, "heap bob"), vec![3, 2]);
+        // Title and description of 2
+        assert_eq!(search(&catalog, "cookie heap"), vec![2]);
+        assert_eq!(search(&catalog, "heap alice cookie"), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn ranks_title_matches_first() {
+        let catalog = catalog();
+
+        // Titles of 1 and 3, newest first, then the description of 2
+        assert_eq!(search(&catalog, "heap"), vec![3, 1, 2]);
+        assert_eq!(search(&catalog, "baby heap"), vec![3]);
+    }
+
+    #[test]
+    fn applies_filters() {
+        let catalog = catalog();
+
+        let mut request = RequestChallengeList::new();
+        request.set_category(Category::new("pwnable"));
+        request.set_status(Status::Solved);
+        let (challenges, page_info) = catalog.search(&request);
+
+        assert!(challenges.is_empty());
+        assert_eq!(page_info.get_count(), 0);
+
+        request.set_status(Status::ToDo);
+        let (challenges, _) = catalog.search(&request);
+        assert_eq!(
+            challenges.iter().map(Challenge::get_id).collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+
+        let mut catalog = catalog();
+        catalog.logged_in = true;
+        catalog.synced_at = DateTime::from_timestamp(1_700_000_000, 0);
+        catalog.save_to(&path).unwrap();
+
+        let loaded = Catalog::load_from(&path).unwrap();
+        assert_eq!(loaded.len(), 4);
+        assert!(loaded.logged_in);
+        assert_eq!(loaded.get_synced_at(), catalog.get_synced_at());
+        // The index is rebuilt on load
+        assert_eq!(search(&loaded, "heap bob"), vec![3, 2]);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn ignores_other_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+
+        catalog().save_to(&path).unwrap();
+        let file = fs::read_to_string(&path).unwrap().replacen(
+            &format!("\"version\":{}", CATALOG_VERSION),
+            "\"version\":0",
+            1,
+        );
+        fs::write(&path, file).unwrap();
+
+        assert!(Catalog::load_from(&path).is_none());
+        assert!(Catalog::load_from(&dir.path().join("missing.json")).is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+pub type Result<T> = std::result::Result<T, DreamhackError>;
+
+#[derive(Debug)]
+pub enum DreamhackError {
+    /// Connection failures, timeouts, invalid URLs, ...
+    Network(reqwest::Error),
+    /// The server answered with an unexpected status code
+    Status { status: StatusCode, body: String },
+    /// The response body is not valid JSON for the expected type
+    Decode(serde_json::Error),
+    /// Missing or expired session (401, 403 or an empty AuthKey)
+    AuthExpired,
+    /// Valid JSON, but without the fields creamhack relies on
+    UnexpectedSchema(String),
+    /// Gave up waiting for the server (e.g. a VM that never becomes ready)
+    Timeout(String),
+    /// Offline mode, and the query has never been fetched
+    NotCached,
+}
+
+impl DreamhackError {
+    /// Turns 401/403 into `AuthExpired`, leaving other status codes to the caller.
+    pub(super) fn check_auth(response: Response) -> Result<Response> {
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(DreamhackError::AuthExpired),
+            _ => Ok(response),
+        }
+    }
+
+    /// Turns 401/403 into `AuthExpired` and other non-success codes into `Status`.
+    pub(super) async fn check_response(response: Response) -> Result<Response> {
+        let response = Self::check_auth(response)?;
+        let status = response.status();
+        match status {
+            status if !status.is_success() => Err(DreamhackError::Status {
+                status,
+                body: response.text().await.unwrap_or_default(),
+            }),
+            _ => Ok(response),
+        }
+    }
+
+    /// Checks the status and decodes the JSON body.
+    pub(super) async fn decode_response<T: DeserializeOwned>(response: Response) -> Result<T> {
+        let text = Self::check_response(response).await?.text().await?;
+
+        #[cfg(debug_assertions)]
+        log::info!("Response: {:?}", text);
+
+        Ok(serde_json::from_str::<T>(&text)?)
+    }
+}
+
+impl Display for DreamhackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DreamhackError::Network(e) => write!(f, "Network error: {}", e),
+            DreamhackError::Status { status, body } if body.is_empty() => {
+                write!(f, "Server returned {}", status)
+            }
+            DreamhackError::Status { status, body } => {
+                write!(f, "Server returned {}: {}", status, body)
+            }
+            DreamhackError::Decode(e) => write!(f, "Failed to decode response: {}", e),
+            DreamhackError::AuthExpired => write!(f, "Session expired, please log in again"),
+            DreamhackError::UnexpectedSchema(detail) => {
+                write!(f, "Unexpected response format: {}", detail)
+            }
+            DreamhackError::Timeout(detail) => write!(f, "Timed out: {}", detail),
+            DreamhackError::NotCached => write!(f, "Offline, and this search is not cached"),
+        }
+    }
+}
+
+impl std::error::Error for DreamhackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DreamhackError::Network(e) => Some(e),
+            DreamhackError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for DreamhackError {
+    fn from(value: reqwest::Error) -> Self {
+        DreamhackError::Network(value)
+    }
+}
+
+impl From<serde_json::Error> for DreamhackError {
+    fn from(value: serde_json::Error) -> Self {
+        DreamhackError::Decode(value)
+    }
+}
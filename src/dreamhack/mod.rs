@@ -1,25 +1,119 @@
 use ratatui::text::Span;
 
+pub mod catalog;
+pub mod error;
 pub mod options;
+pub mod query;
 
-#[allow(dead_code)]
-const CHALLENGES_URL: &str = "https://dreamhack.io/api/v1/wargame/challenges/";
+const CHALLENGES_PATH: &str = "wargame/challenges/";
 
-#[allow(dead_code)]
-const LOGIN_URL: &str = "https://dreamhack.io/api/v1/auth/login/";
+const LOGIN_PATH: &str = "auth/login/";
 
 pub trait ToRequestString {
     fn to_request_string(&self) -> String;
 }
 
 pub trait ToColorString {
-    fn to_color_string(&self) -> Span;
+    fn to_color_string(&self) -> Span<'_>;
+}
+
+pub mod client {
+    use reqwest::{Client, Url};
+
+    const DEFAULT_BASE_URL: &str = "https://dreamhack.io/api/v1/";
+
+    /// Environment variable that overrides the API base URL (e.g. a local mock server)
+    pub const BASE_URL_ENV: &str = "CREAMHACK_API_URL";
+
+    /// Shared HTTP client for every Dreamhack API call.
+    ///
+    /// `reqwest::Client` is reference counted, so cloning this is cheap
+    /// and keeps the connection pool shared.
+    #[derive(Debug, Clone)]
+    pub struct DreamhackClient {
+        http: Client,
+        base_url: Url,
+    }
+
+    impl DreamhackClient {
+        /// Base URL priority: `CREAMHACK_API_URL` > `base_url` (config) > dreamhack.io
+        pub fn new(base_url: Option<&str>) -> Self {
+            let base_url = std::env::var(BASE_URL_ENV)
+                .ok()
+                .or(base_url.map(str::to_owned))
+                .and_then(|url| Self::parse_base_url(&url))
+                .unwrap_or_else(|| Url::parse(DEFAULT_BASE_URL).unwrap());
+
+            #[cfg(debug_assertions)]
+            log::info!("Dreamhack API base URL: {}", base_url);
+
+            DreamhackClient {
+                http: Client::new(),
+                base_url,
+            }
+        }
+
+        /// `Url::join` drops the last path segment unless it ends with '/'.
+        fn parse_base_url(url: &str) -> Option<Url> {
+            let url = if url.ends_with('/') {
+                url.to_owned()
+            } else {
+                format!("{}/", url)
+            };
+
+            let parsed = Url::parse(&url);
+
+            #[cfg(debug_assertions)]
+            if let Err(e) = &parsed {
+                log::error!("Invalid API base URL {}: {:?}", url, e);
+            }
+
+            parsed.ok()
+        }
+
+        pub fn http(&self) -> &Client {
+            &self.http
+        }
+
+        pub fn base_url(&self) -> &Url {
+            &self.base_url
+        }
+
+        /// Resolves an API path (e.g. "wargame/challenges/") against the base URL.
+        pub fn endpoint(&self, path: &str) -> Url {
+            self.base_url
+                .join(path)
+                .expect("API path must be a valid relative URL")
+        }
+
+        pub fn challenge_endpoint(&self, id: u64, action: &str) -> Url {
+            self.endpoint(&format!("{}{}/{}", super::CHALLENGES_PATH, id, action))
+        }
+    }
+
+    impl Default for DreamhackClient {
+        fn default() -> Self {
+            Self::new(None)
+        }
+    }
 }
 
 pub mod auth {
+    use std::{
+        future::Future,
+        sync::{Arc, Mutex},
+    };
+
+    use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
     use serde::{Deserialize, Serialize};
 
-    use super::LOGIN_URL;
+    use super::{
+        client::DreamhackClient,
+        error::{DreamhackError, Result},
+        LOGIN_PATH,
+    };
+
+    type LoginHook = Arc<dyn Fn(&Auth) + Send + Sync>;
 
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct Login {
@@ -28,21 +122,32 @@ pub mod auth {
         save_login: bool,
     }
 
-    #[derive(Debug, Clone, Default)]
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
     pub struct AuthKey(String);
 
-    #[derive(Debug, Clone, Default)]
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
     pub struct AuthCookies {
         csrf_token: String,
         sessionid: String,
     }
 
-    #[derive(Debug, Clone, Default)]
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
     pub struct Auth {
         key: AuthKey,
         cookies: AuthCookies,
     }
 
+    /// Login state shared by the UI and the background tasks.
+    ///
+    /// Keeps the credentials so an expired session can be renewed without asking the user.
+    #[derive(Clone, Default)]
+    pub struct Session {
+        auth: Arc<Mutex<Auth>>,
+        login: Option<Arc<Login>>,
+        login_lock: Arc<tokio::sync::Mutex<()>>,
+        on_login: Option<LoginHook>,
+    }
+
     impl AuthCookies {
         pub fn to_request(&self) -> String {
             format!(
@@ -65,49 +170,158 @@ pub mod auth {
             &self.cookies
         }
 
-        pub fn send_login(email: &str, password: &str, save_login: bool) -> Option<Auth> {
+        /// Headers required by the authenticated endpoints.
+        pub fn to_headers(&self) -> Result<HeaderMap> {
+            if self.get_key().is_empty() {
+                #[cfg(debug_assertions)]
+                log::error!("AuthKey is empty");
+                return Err(DreamhackError::AuthExpired);
+            }
+
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                COOKIE,
+                HeaderValue::from_str(&format!(
+                    "i18n_redirected=ko; {}",
+                    self.cookies.to_request()
+                ))
+                .map_err(|_| DreamhackError::AuthExpired)?,
+            );
+
+            headers.insert(
+                "X-Csrftoken",
+                HeaderValue::from_str(self.cookies.get_csrf_token())
+                    .map_err(|_| DreamhackError::AuthExpired)?,
+            );
+
+            Ok(headers)
+        }
+
+        pub async fn send_login(
+            client: &DreamhackClient,
+            email: &str,
+            password: &str,
+            save_login: bool,
+        ) -> Result<Auth> {
             let login = Login {
                 email: email.to_owned(),
                 password: password.to_owned(),
                 save_login,
             };
 
-            match reqwest::blocking::Client::new()
-                .post(LOGIN_URL)
+            let response = client
+                .http()
+                .post(client.endpoint(LOGIN_PATH))
                 .json(&login)
                 .send()
-            {
-                Ok(response) => {
-                    let mut cookies = AuthCookies::default();
-                    for cookie in response.cookies() {
-                        match cookie.name() {
-                            "csrf_token" => {
-                                cookies.csrf_token = cookie.value().to_owned();
-                            }
-                            "sessionid" => {
-                                cookies.sessionid = cookie.value().to_owned();
-                            }
-                            _ => {}
-                        }
+                .await?;
+            let response = DreamhackError::check_response(response).await?;
+
+            let mut cookies = AuthCookies::default();
+            for cookie in response.cookies() {
+                match cookie.name() {
+                    "csrf_token" => {
+                        cookies.csrf_token = cookie.value().to_owned();
                     }
+                    "sessionid" => {
+                        cookies.sessionid = cookie.value().to_owned();
+                    }
+                    _ => {}
+                }
+            }
+
+            let key = response
+                .json::<serde_json::Value>()
+                .await?
+                .get("key")
+                .and_then(|key| key.as_str())
+                .map(|key| AuthKey(key.to_owned()))
+                .ok_or_else(|| {
+                    DreamhackError::UnexpectedSchema("login response has no `key`".to_owned())
+                })?;
+
+            Ok(Auth { key, cookies })
+        }
+    }
+
+    impl Session {
+        /// `auth` is a session restored from a previous run, if any.
+        pub fn new(email: &str, password: &str, auth: Option<Auth>) -> Self {
+            Session {
+                auth: Arc::new(Mutex::new(auth.unwrap_or_default())),
+                login: Some(Arc::new(Login {
+                    email: email.to_owned(),
+                    password: password.to_owned(),
+                    // The session is persisted, so ask for a long-lived one
+                    save_login: true,
+                })),
+                login_lock: Arc::new(tokio::sync::Mutex::new(())),
+                on_login: None,
+            }
+        }
+
+        /// Called with the new `Auth` after every successful login (e.g. to persist it).
+        pub fn with_login_hook(mut self, hook: impl Fn(&Auth) + Send + Sync + 'static) -> Self {
+            self.on_login = Some(Arc::new(hook));
+            self
+        }
+
+        pub fn get_auth(&self) -> Auth {
+            self.auth.lock().unwrap().clone()
+        }
+
+        pub fn is_logged_in(&self) -> bool {
+            !self.auth.lock().unwrap().get_key().is_empty()
+        }
+
+        pub async fn login(&self, client: &DreamhackClient) -> Result<Auth> {
+            let login = self.login.as_ref().ok_or(DreamhackError::AuthExpired)?;
+            let auth =
+                Auth::send_login(client, &login.email, &login.password, login.save_login).await?;
+
+            *self.auth.lock().unwrap() = auth.clone();
+            if let Some(hook) = &self.on_login {
+                hook(&auth);
+            }
+
+            #[cfg(debug_assertions)]
+            log::info!("Logged in as {}", login.email);
+
+            Ok(auth)
+        }
+
+        /// Logs in again unless another task already renewed the `expired` session.
+        async fn renew(&self, client: &DreamhackClient, expired: &Auth) -> Result<Auth> {
+            let _guard = self.login_lock.lock().await;
 
-                    let key = AuthKey(
-                        response
-                            .json::<serde_json::Value>()
-                            .unwrap()
-                            .get("key")
-                            .unwrap()
-                            .as_str()
-                            .unwrap()
-                            .to_owned(),
-                    );
-
-                    Some(Auth { key, cookies })
+            let current = self.get_auth();
+            if current != *expired && !current.get_key().is_empty() {
+                return Ok(current);
+            }
+            self.login(client).await
+        }
+
+        /// Runs `request` with the current session.
+        /// When the server answers 401/403, logs in again and retries once.
+        pub async fn retry_on_expired<T, F, Fut>(
+            &self,
+            client: &DreamhackClient,
+            request: F,
+        ) -> Result<T>
+        where
+            F: Fn(Auth) -> Fut,
+            Fut: Future<Output = Result<T>>,
+        {
+            let auth = self.get_auth();
+            match request(auth.clone()).await {
+                Err(DreamhackError::AuthExpired) => {
+                    #[cfg(debug_assertions)]
+                    log::info!("Session expired, logging in again");
+
+                    let auth = self.renew(client, &auth).await?;
+                    request(auth).await
                 }
-                Err(e) => {
-                    dbg!(e);
-                    None
-                } // TODO: Error Handling
+                result => result,
             }
         }
     }
@@ -116,12 +330,20 @@ pub mod auth {
 pub mod challenge {
     #![allow(dead_code)]
 
+    use std::collections::BTreeMap;
+
+    use chrono::{DateTime, Utc};
     use handle::{Challenge, PageInfo};
-    use options::{Category, Difficulty, Orderings, Status};
+    use options::{Category, ChallengeType, Difficulty, Orderings, Scope, Status};
     use reqwest::Url;
     use serde::{Deserialize, Serialize};
 
-    use super::*;
+    use super::{
+        auth::Auth,
+        client::DreamhackClient,
+        error::{DreamhackError, Result},
+        *,
+    };
 
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct ChallengeResponseData {
@@ -157,7 +379,7 @@ pub mod challenge {
         public_at: String,
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
     pub struct Author {
         id: u64,
         nickname: String,
@@ -171,7 +393,7 @@ pub mod challenge {
         contributions: Contributions,
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
     pub struct Contributions {
         level: u64,
         exp: u64,
@@ -182,34 +404,66 @@ pub mod challenge {
         totals: u64,
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
     pub struct Wargame {
         rank: u64,
         score: u64,
+        #[serde(default)]
         category: CategoryInfo,
         last_solved_at: Option<serde_json::Value>,
     }
 
-    // 차후 업데이트에 따라 필드가 추가될 수 있습니다.
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub struct CategoryInfo {
-        pwnable: AuthorWargameCategory,
-        reversing: AuthorWargameCategory,
-        web: AuthorWargameCategory,
-        crypto: AuthorWargameCategory,
-    }
+    /// Score and rank of the author per category, keyed by the category name of the API.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    #[serde(transparent)]
+    pub struct CategoryInfo(BTreeMap<String, AuthorWargameCategory>);
 
-    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
     pub struct AuthorWargameCategory {
+        #[serde(default)]
         score: u64,
+        #[serde(default)]
         rank: u64,
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone)]
+    impl CategoryInfo {
+        pub fn get(&self, category: &Category) -> Option<&AuthorWargameCategory> {
+            self.0.get(&category.to_request_string())
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
     pub struct HitCount {
         hits: u64,
     }
 
+    impl Author {
+        pub fn get_nickname(&self) -> &str {
+            &self.nickname
+        }
+
+        pub fn is_staff(&self) -> bool {
+            self.is_staff
+        }
+
+        /// contribution level shown next to the nickname on Dreamhack
+        pub fn get_level(&self) -> u64 {
+            self.contributions.level
+        }
+
+        pub fn get_wargame_rank(&self) -> u64 {
+            self.wargame.rank
+        }
+
+        pub fn get_wargame_categories(&self) -> &CategoryInfo {
+            &self.wargame.category
+        }
+
+        pub fn get_country(&self) -> Option<&str> {
+            self.country.as_deref()
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct ChallengeListResponse {
         count: u32,
@@ -219,6 +473,18 @@ pub mod challenge {
         pub results: Vec<ChallengeResponseData>,
     }
 
+    impl ChallengeResponseData {
+        pub fn get_id(&self) -> u64 {
+            self.id
+        }
+    }
+
+    impl ChallengeListResponse {
+        pub fn has_next(&self) -> bool {
+            self.next.is_some()
+        }
+    }
+
     pub struct RequestChallengeList {
         options: ChallengeOptions,
     }
@@ -227,10 +493,10 @@ pub mod challenge {
         page: u64,
         search: Option<String>,
         ordering: Option<Orderings>,
-        scope: Option<String>,
+        scope: Option<Scope>,
         category: Option<Category>,
         difficulty: Option<Difficulty>,
-        type_value: Option<String>,
+        type_value: Option<ChallengeType>,
         status: Option<Status>,
         page_size: Option<u64>,
     }
@@ -268,6 +534,14 @@ pub mod challenge {
             self.options.ordering = Some(ordering);
         }
 
+        pub fn set_type(&mut self, type_value: ChallengeType) {
+            self.options.type_value = Some(type_value);
+        }
+
+        pub fn set_scope(&mut self, scope: Scope) {
+            self.options.scope = Some(scope);
+        }
+
         pub fn set_page(&mut self, page: u64) {
             self.options.page = page;
         }
@@ -280,105 +554,365 @@ pub mod challenge {
             self.options.page_size = Some(page_size);
         }
 
-        pub fn send_request(&mut self) -> Option<(Vec<Challenge>, PageInfo)> {
-            let url = Url::parse_with_params(
-                CHALLENGES_URL,
-                [
-                    (
-                        "ordering",
-                        self.options
-                            .ordering
-                            .as_ref()
-                            .unwrap_or(&Orderings::Newist)
-                            .to_request_string(),
-                    ),
-                    (
-                        "category",
-                        self.options
-                            .category
-                            .as_ref()
-                            .unwrap_or(&Category::All)
-                            .to_request_string(),
-                    ),
-                    (
-                        "status",
-                        self.options
-                            .status
-                            .as_ref()
-                            .unwrap_or(&Status::All)
-                            .to_request_string(),
-                    ),
-                    (
-                        "difficulty",
-                        self.options
-                            .difficulty
-                            .as_ref()
-                            .unwrap_or(&Difficulty::All)
-                            .to_request_string(),
-                    ),
-                    ("page", self.options.page.to_string()),
-                    (
-                        "search",
-                        self.options
-                            .search
-                            .as_ref()
-                            .unwrap_or(&"".to_owned())
-                            .to_owned(),
-                    ), //
-                    ("type", "".to_string()),  // type 기능은 미구현
-                    ("scope", "".to_string()), // scope 기능은 미구현
-                    (
-                        "page_size",
-                        self.options.page_size.unwrap_or(20).to_string(),
-                    ),
-                ],
-            )
-            .expect("Failed to parse URL");
-
-            match serde_json::from_str::<ChallengeListResponse>(
-                reqwest::blocking::get(url)
-                    .unwrap()
-                    .text()
-                    .unwrap_or_default()
-                    .as_str(),
-            ) {
-                Ok(response) => {
-                    let challenges = response
-                        .results
-                        .iter()
-                        .map(|challenge| Challenge::from(challenge.clone()))
-                        .collect::<Vec<Challenge>>();
-
-                    let page_info = PageInfo {
-                        page_index: self.options.page,
-                        count: response.count,
-                        page_size: response.page_size,
-                        next: response.next,
-                        previous: response.previous,
-                    };
+        pub fn get_search(&self) -> &str {
+            self.options.search.as_deref().unwrap_or_default()
+        }
+
+        pub fn get_category(&self) -> Category {
+            self.options.category.clone().unwrap_or(Category::All)
+        }
+
+        pub fn get_difficulty(&self) -> Difficulty {
+            self.options.difficulty.unwrap_or(Difficulty::All)
+        }
+
+        pub fn get_status(&self) -> Status {
+            self.options.status.unwrap_or(Status::All)
+        }
+
+        pub fn get_ordering(&self) -> Orderings {
+            self.options.ordering.unwrap_or(Orderings::Newist)
+        }
+
+        pub fn get_type(&self) -> ChallengeType {
+            self.options.type_value.unwrap_or(ChallengeType::All)
+        }
+
+        /// The Beginner difficulty is the beginner track, it is not a level.
+        pub fn get_scope(&self) -> Scope {
+            match (self.options.scope, self.get_difficulty()) {
+                (None | Some(Scope::All), Difficulty::Beginner) => Scope::Beginner,
+                (scope, _) => scope.unwrap_or(Scope::All),
+            }
+        }
+
+        /// Type and scope, checked against the flags of each challenge.
+        ///
+        /// The API ignores filters it does not know, so results are checked too.
+        pub fn matches_flags(&self, challenge: &Challenge) -> bool {
+            let flags = challenge.get_metadata().get_flags();
+            let type_value = match self.get_type() {
+                ChallengeType::All => true,
+                ChallengeType::Official => flags.is_official(),
+                ChallengeType::Community => !flags.is_official(),
+            };
+            let scope = match self.get_scope() {
+                Scope::All => true,
+                Scope::Beginner => flags.is_beginner(),
+            };
+
+            type_value && scope
+        }
+
+        /// Key of the response in the on-disk cache.
+        ///
+        /// Responses of a logged in user carry their solved/attempted flags, so they
+        /// are cached apart from anonymous ones.
+        pub fn cache_key(&self, client: &DreamhackClient, logged_in: bool) -> String {
+            let user = if logged_in { "user" } else { "anonymous" };
+            format!("{} {}", user, self.url(client))
+        }
+
+        /// Last response to the same query, with the time it was fetched.
+        ///
+        /// Logged in users fall back to the anonymous response, which lacks their flags.
+        pub fn load_cached(
+            &self,
+            client: &DreamhackClient,
+            logged_in: bool,
+        ) -> Option<(Vec<Challenge>, PageInfo, DateTime<Utc>)> {
+            let (response, fetched_at) =
+                cache::load(&self.cache_key(client, logged_in)).or_else(|| match logged_in {
+                    true => cache::load(&self.cache_key(client, false)),
+                    false => None,
+                })?;
+            let (challenges, page_info) = self.to_page(response);
+            Some((challenges, page_info, fetched_at))
+        }
+
+        /// With `auth`, the solved/attempted flags of each challenge reflect the user.
+        ///
+        /// Successful responses are written to the on-disk cache.
+        pub async fn send_request(
+            &self,
+            client: &DreamhackClient,
+            auth: Option<&Auth>,
+        ) -> Result<(Vec<Challenge>, PageInfo)> {
+            let response = self.fetch_response(client, auth).await?;
+            cache::store(&self.cache_key(client, auth.is_some()), &response);
+
+            Ok(self.to_page(response))
+        }
+
+        /// Raw response of the API, bypassing the cache.
+        pub async fn fetch_response(
+            &self,
+            client: &DreamhackClient,
+            auth: Option<&Auth>,
+        ) -> Result<ChallengeListResponse> {
+            let mut request = client.http().get(self.url(client));
+            if let Some(auth) = auth {
+                request = request
+                    .bearer_auth(auth.get_key())
+                    .headers(auth.to_headers()?);
+            }
+
+            let response = request.send().await?;
+            DreamhackError::decode_response::<ChallengeListResponse>(response).await
+        }
+
+        fn to_page(&self, response: ChallengeListResponse) -> (Vec<Challenge>, PageInfo) {
+            let challenges = response
+                .results
+                .into_iter()
+                .map(Challenge::from)
+                .filter(|challenge| self.matches_flags(challenge))
+                .collect::<Vec<Challenge>>();
+
+            let page_info = PageInfo {
+                page_index: self.options.page,
+                count: response.count,
+                page_size: response.page_size,
+                next: response.next,
+                previous: response.previous,
+            };
+
+            (challenges, page_info)
+        }
+
+        fn url(&self, client: &DreamhackClient) -> Url {
+            let mut url = client.endpoint(CHALLENGES_PATH);
+            url.query_pairs_mut().extend_pairs([
+                (
+                    "ordering",
+                    self.options
+                        .ordering
+                        .as_ref()
+                        .unwrap_or(&Orderings::Newist)
+                        .to_request_string(),
+                ),
+                (
+                    "category",
+                    self.options
+                        .category
+                        .as_ref()
+                        .unwrap_or(&Category::All)
+                        .to_request_string(),
+                ),
+                (
+                    "status",
+                    self.options
+                        .status
+                        .as_ref()
+                        .unwrap_or(&Status::All)
+                        .to_request_string(),
+                ),
+                (
+                    "difficulty",
+                    self.options
+                        .difficulty
+                        .as_ref()
+                        .unwrap_or(&Difficulty::All)
+                        .to_request_string(),
+                ),
+                ("page", self.options.page.to_string()),
+                (
+                    "search",
+                    self.options
+                        .search
+                        .as_ref()
+                        .unwrap_or(&"".to_owned())
+                        .to_owned(),
+                ), //
+                ("type", self.get_type().to_request_string()),
+                ("scope", self.get_scope().to_request_string()),
+                (
+                    "page_size",
+                    self.options.page_size.unwrap_or(20).to_string(),
+                ),
+            ]);
+            url
+        }
+    }
+
+    /// Order of `ordering`, for results that were not sorted by the API.
+    pub fn compare_by_ordering(
+        a: &Challenge,
+        b: &Challenge,
+        ordering: Orderings,
+    ) -> std::cmp::Ordering {
+        let solvers = |challenge: &Challenge| challenge.get_metadata().get_stats().solvers;
+        match ordering {
+            Orderings::Newist => {
+                let newest = |challenge: &Challenge| {
+                    (challenge.get_metadata().get_public_at(), challenge.get_id())
+                };
+                newest(b).cmp(&newest(a))
+            }
+            Orderings::MostSolved => solvers(b).cmp(&solvers(a)),
+            Orderings::LeastSolved => solvers(a).cmp(&solvers(b)),
+        }
+    }
+
+    /// Joins the same page of several requests (e.g. one per category) into one page.
+    ///
+    /// A challenge listed by more than one request is kept once. The page has a
+    /// next/previous page when any of the requests has.
+    pub fn merge_pages(
+        pages: Vec<(Vec<Challenge>, PageInfo)>,
+        ordering: Orderings,
+    ) -> (Vec<Challenge>, PageInfo) {
+        if pages.len() == 1 {
+            // Keep the order of the API
+            return pages.into_iter().next().unwrap_or_default();
+        }
+
+        let mut merged = PageInfo::default();
+        let mut challenges: Vec<Challenge> = Vec::new();
+        for (page, page_info) in pages {
+            merged.page_index = merged.page_index.max(page_info.page_index);
+            merged.count += page_info.count;
+            merged.page_size += page_info.page_size;
+            merged.next = merged.next.or(page_info.next);
+            merged.previous = merged.previous.or(page_info.previous);
+
+            for challenge in page {
+                if !challenges
+                    .iter()
+                    .any(|merged| merged.get_id() == challenge.get_id())
+                {
+                    challenges.push(challenge);
+                }
+            }
+        }
+
+        challenges.sort_by(|a, b| compare_by_ordering(a, b, ordering));
+        (challenges, merged)
+    }
 
-                    Some((challenges, page_info))
+    /// On-disk cache of challenge list responses, one file per query.
+    pub mod cache {
+        use std::{
+            fs,
+            path::{Path, PathBuf},
+        };
+
+        use chrono::{DateTime, Utc};
+        use serde::{Deserialize, Serialize};
+
+        use super::ChallengeListResponse;
+
+        /// Oldest entries are removed beyond this many cached queries
+        const MAX_ENTRIES: usize = 512;
+
+        #[derive(Serialize, Deserialize)]
+        struct CacheEntry {
+            key: String,
+            /// Unix timestamp in seconds
+            fetched_at: i64,
+            response: ChallengeListResponse,
+        }
+
+        pub fn cache_dir() -> Option<PathBuf> {
+            let mut path = dirs::data_dir()?;
+            path.push("creamhack/cache/challenges");
+            Some(path)
+        }
+
+        /// FNV-1a, stable across builds unlike `DefaultHasher`.
+        fn file_name(key: &str) -> String {
+            let hash = key.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+            format!("{:016x}.json", hash)
+        }
+
+        pub(super) fn load(key: &str) -> Option<(ChallengeListResponse, DateTime<Utc>)> {
+            let path = cache_dir()?.join(file_name(key));
+            let entry = fs::read_to_string(path).ok()?;
+            let entry = serde_json::from_str::<CacheEntry>(&entry).ok()?;
+
+            // Hash collision
+            if entry.key != key {
+                return None;
+            }
+            Some((
+                entry.response,
+                DateTime::from_timestamp(entry.fetched_at, 0)?,
+            ))
+        }
+
+        /// Failing to cache is not an error for the request, it is only logged.
+        pub(super) fn store(key: &str, response: &ChallengeListResponse) {
+            let result = (|| -> anyhow::Result<()> {
+                let dir = cache_dir().ok_or(anyhow::anyhow!("No data directory"))?;
+                fs::create_dir_all(&dir)?;
+
+                let entry = CacheEntry {
+                    key: key.to_string(),
+                    fetched_at: Utc::now().timestamp(),
+                    response: response.clone(),
+                };
+                // Write then rename, so a crash never leaves a truncated entry
+                let path = dir.join(file_name(key));
+                let temp = path.with_extension("json.tmp");
+                fs::write(&temp, serde_json::to_string(&entry)?)?;
+                fs::rename(temp, path)?;
+
+                prune(&dir)
+            })();
+
+            match result {
+                Ok(()) => {}
+                #[allow(unused_variables)]
+                Err(e) => {
+                    #[cfg(debug_assertions)]
+                    log::warn!("Failed to cache challenge list: {:#}", e);
                 }
-                Err(_e) => None, // TODO: Error Handling
             }
         }
+
+        fn prune(dir: &Path) -> anyhow::Result<()> {
+            let mut entries = fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+                .collect::<Vec<_>>();
+            if entries.len() <= MAX_ENTRIES {
+                return Ok(());
+            }
+
+            entries.sort();
+            for (_, path) in entries.iter().take(entries.len() - MAX_ENTRIES) {
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        }
     }
 
     pub mod handle {
 
-        use core::panic;
+        use std::time::Duration;
 
-        use anyhow::Context;
-        use ratatui::text::{Line, Text};
-        use reqwest::{
-            blocking::Client,
-            header::{HeaderMap, HeaderValue, COOKIE},
+        use chrono::{DateTime, FixedOffset, Utc};
+        use ratatui::{
+            style::{Color, Style, Stylize},
+            text::{Line, Span, Text},
         };
+        use reqwest::StatusCode;
 
         /* Handler for Challenge */
-        use super::{auth::Auth, vm_info::MachineInfo, ChallengeResponseData, ToColorString};
+        use super::{
+            auth::Auth,
+            client::DreamhackClient,
+            error::{DreamhackError, Result},
+            vm_info::MachineInfo,
+            Author, ChallengeResponseData, ToColorString,
+        };
         use crate::dreamhack::options::Difficulty;
 
+        const VM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+        const VM_READY_TIMEOUT: Duration = Duration::from_secs(120);
+
         #[derive(Debug, Clone, Default, PartialEq, Eq)]
         pub struct PageInfo {
             pub(super) page_index: u64,
@@ -417,6 +951,17 @@ pub mod challenge {
                 self.page_index
             }
 
+            /// All `count` results on a single page, e.g. of a local search.
+            pub fn single_page(count: usize) -> Self {
+                PageInfo {
+                    page_index: 1,
+                    count: count as u32,
+                    page_size: count as u32,
+                    next: None,
+                    previous: None,
+                }
+            }
+
             pub fn next_page(&mut self) {
                 if self.has_next() {
                     self.page_index += 1
@@ -435,7 +980,7 @@ pub mod challenge {
             title: String,
             description: String,
             difficulty: u64,
-            author: String,
+            author: Author,
             tags: Vec<String>,
         }
 
@@ -443,9 +988,24 @@ pub mod challenge {
         pub struct ChallengeMetadata {
             repository: String,
             public: String,
+            /// RFC 3339 timestamps
+            created_at: String,
+            public_at: String,
+            deployed: String,
+            stats: ChallengeStats,
             flags: Flags,
         }
 
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct ChallengeStats {
+            pub solvers: u64,
+            pub writeups: u64,
+            pub votes: u64,
+            pub questions: u64,
+            pub comments: u64,
+            pub hits: u64,
+        }
+
         #[derive(Debug, Clone, PartialEq, Eq)]
         pub struct Flags {
             is_completed: bool,
@@ -479,156 +1039,244 @@ pub mod challenge {
                 &self.metadata
             }
 
-            pub fn download_challenge(&self) -> Vec<u8> {
-                let response = reqwest::blocking::get(self.metadata.get_public())
-                    .expect("Failed to download challenge file")
-                    .bytes()
-                    .expect("Failed to get bytes from response");
-
-                response.to_vec()
+            /// Fetches a single challenge (`wargame/challenges/<id>/`).
+            pub async fn fetch(client: &DreamhackClient, id: u64) -> Result<Challenge> {
+                let response = client
+                    .http()
+                    .get(client.challenge_endpoint(id, ""))
+                    .send()
+                    .await?;
+
+                Ok(
+                    DreamhackError::decode_response::<ChallengeResponseData>(response)
+                        .await?
+                        .into(),
+                )
             }
 
-            pub fn create_vm(&self, auth: &Auth) -> bool {
-                if auth.get_key().is_empty() {
-                    #[cfg(debug_assertions)]
-                    log::error!("AuthKey is empty");
-                    return false;
+            /// Starts the download of the challenge files, read the body in chunks.
+            ///
+            /// A non-zero `offset` asks for the rest of the file only, the server may
+            /// still answer with the whole file (200 instead of 206).
+            pub async fn download_challenge(
+                &self,
+                client: &DreamhackClient,
+                offset: u64,
+            ) -> Result<reqwest::Response> {
+                let mut request = client.http().get(self.metadata.get_public());
+                if offset > 0 {
+                    request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
                 }
+                DreamhackError::check_response(request.send().await?).await
+            }
 
-                let mut headers = HeaderMap::new();
-                headers.insert(
-                    COOKIE,
-                    HeaderValue::from_str(&format!(
-                        "i18n_redirected=ko; {}",
-                        auth.get_cookies().to_request()
-                    ))
-                    .unwrap(),
-                );
-
-                headers.insert(
-                    "X-Csrftoken",
-                    HeaderValue::from_str(auth.get_cookies().get_csrf_token()).unwrap(),
-                );
-
-                let request = Client::new()
-                    .post(format!(
-                        "https://dreamhack.io/api/v1/wargame/challenges/{}/live/",
-                        self.id
-                    ))
+            pub async fn create_vm(&self, client: &DreamhackClient, auth: &Auth) -> Result<()> {
+                let request = client
+                    .http()
+                    .post(client.challenge_endpoint(self.id, "live/"))
                     .bearer_auth(auth.get_key())
-                    .headers(headers);
+                    .headers(auth.to_headers()?);
 
                 #[cfg(debug_assertions)]
                 log::info!("Request: {:?}", request);
 
-                let response = request.send().context("Failed to create VM").unwrap();
-
-                let status = response.status();
-                #[cfg(debug_assertions)]
-                let response_text = response
-                    .text()
-                    .unwrap_or_else(|_| "Failed to read response body".to_string());
+                #[allow(unused_variables)]
+                let response = DreamhackError::check_response(request.send().await?).await?;
 
                 #[cfg(debug_assertions)]
-                if !status.is_success() {
-                    log::error!("Failed to create VM, status: {}", status);
-                    log::error!("Response: {:?}", response_text);
-                } else {
+                {
                     log::info!("VM created successfully");
-                    log::info!("Response: {:?}", response_text);
+                    log::info!("Response: {:?}", response.text().await);
                 }
 
-                status.is_success()
+                Ok(())
             }
 
-            pub fn get_vm_info(&self, auth: &Auth) -> MachineInfo {
-                if auth.get_key().is_empty() {
-                    #[cfg(debug_assertions)]
-                    log::error!("AuthKey is empty");
-                    panic!("AuthKey is empty");
-                }
+            pub async fn get_vm_info(
+                &self,
+                client: &DreamhackClient,
+                auth: &Auth,
+            ) -> Result<MachineInfo> {
+                let request = client
+                    .http()
+                    .get(client.challenge_endpoint(self.id, "live/"))
+                    .bearer_auth(auth.get_key())
+                    .headers(auth.to_headers()?);
 
-                let mut headers = HeaderMap::new();
-                headers.insert(
-                    COOKIE,
-                    HeaderValue::from_str(&format!(
-                        "i18n_redirected=ko; {}",
-                        auth.get_cookies().to_request()
-                    ))
-                    .unwrap(),
-                );
+                #[cfg(debug_assertions)]
+                log::info!("Request: {:?}", request);
+
+                DreamhackError::decode_response::<MachineInfo>(request.send().await?).await
+            }
 
-                let request = Client::new()
-                    .get(format!(
-                        "https://dreamhack.io/api/v1/wargame/challenges/{}/live/",
-                        self.id
-                    ))
+            /// Extends the remaining time of the running VM.
+            pub async fn extend_vm(&self, client: &DreamhackClient, auth: &Auth) -> Result<()> {
+                let request = client
+                    .http()
+                    .post(client.challenge_endpoint(self.id, "live/extend/"))
                     .bearer_auth(auth.get_key())
-                    .headers(headers);
+                    .headers(auth.to_headers()?);
 
                 #[cfg(debug_assertions)]
                 log::info!("Request: {:?}", request);
 
-                let response = request.send().context("Failed to get VM info").unwrap();
+                DreamhackError::check_response(request.send().await?).await?;
+                Ok(())
+            }
 
-                let text = response.text();
+            pub async fn terminate_vm(&self, client: &DreamhackClient, auth: &Auth) -> Result<()> {
+                let request = client
+                    .http()
+                    .delete(client.challenge_endpoint(self.id, "live/"))
+                    .bearer_auth(auth.get_key())
+                    .headers(auth.to_headers()?);
 
                 #[cfg(debug_assertions)]
-                log::info!("Response: {:?}", text);
+                log::info!("Request: {:?}", request);
 
-                serde_json::from_str::<MachineInfo>(
-                    text.context("Failed to get text from response")
-                        .unwrap()
-                        .as_str(),
-                )
-                .context("Failed to parse JSON")
-                .unwrap()
+                DreamhackError::check_response(request.send().await?).await?;
+                Ok(())
             }
 
-            pub fn submit_flag(&self, auth: Auth, flag: &str) -> bool {
-                if auth.get_key().is_empty() {
-                    #[cfg(debug_assertions)]
-                    log::error!("AuthKey is empty");
-                    panic!("AuthKey is empty");
-                }
+            /// Polls the VM until it reports running and its port accepts connections.
+            /// Failed polls (timeouts, 5xx, a 404 while the VM is being set up) are retried
+            /// until the deadline, and the last error is returned once it expires. An
+            /// expired session is returned right away so the caller can renew it.
+            pub async fn wait_for_vm(
+                &self,
+                client: &DreamhackClient,
+                auth: &Auth,
+            ) -> Result<MachineInfo> {
+                let deadline = tokio::time::Instant::now() + VM_READY_TIMEOUT;
+
+                loop {
+                    let error = match self.get_vm_info(client, auth).await {
+                        Ok(vm_info) if vm_info.is_running() && vm_info.is_reachable().await => {
+                            return Ok(vm_info);
+                        }
+                        Ok(vm_info) => DreamhackError::Timeout(format!(
+                            "VM is not ready (state: {})",
+                            vm_info.get_state()
+                        )),
+                        Err(DreamhackError::AuthExpired) => {
+                            return Err(DreamhackError::AuthExpired)
+                        }
+                        Err(e) => {
+                            #[cfg(debug_assertions)]
+                            log::warn!("Polling VM {} failed: {}", self.id, e);
+                            e
+                        }
+                    };
 
-                let mut headers = HeaderMap::new();
-                headers.insert(
-                    COOKIE,
-                    HeaderValue::from_str(&format!(
-                        "i18n_redirected=ko; {}",
-                        auth.get_cookies().to_request()
-                    ))
-                    .unwrap(),
-                );
+                    if tokio::time::Instant::now() + VM_POLL_INTERVAL > deadline {
+                        return Err(error);
+                    }
+                    tokio::time::sleep(VM_POLL_INTERVAL).await;
+                }
+            }
 
-                let request = reqwest::blocking::Client::new()
-                    .post(format!(
-                        "https://dreamhack.io/api/v1/wargame/challenges/{}/auth",
-                        self.id
-                    ))
+            pub async fn submit_flag(
+                &self,
+                client: &DreamhackClient,
+                auth: &Auth,
+                flag: &str,
+            ) -> Result<FlagResult> {
+                let request = client
+                    .http()
+                    .post(client.challenge_endpoint(self.id, "auth"))
                     .bearer_auth(auth.get_key())
+                    .headers(auth.to_headers()?)
                     .json(&serde_json::json!({
                         "flag": flag
                     }));
 
-                let response = request.send().context("Failed to send flag").unwrap();
-
+                let response = DreamhackError::check_auth(request.send().await?)?;
                 let status = response.status();
-                #[cfg(debug_assertions)]
-                let response_text = response
-                    .text()
-                    .unwrap_or_else(|_| "Failed to read response body".to_string());
+                let response_text = response.text().await?;
 
                 #[cfg(debug_assertions)]
-                if !status.is_success() {
-                    log::error!("Failed to create VM, status: {}", status);
-                    log::error!("Response: {:?}", response_text);
-                } else {
-                    log::info!("VM created successfully");
+                log::info!(
+                    "Flag submission status: {}, Response: {:?}",
+                    status,
+                    response_text
+                );
+
+                FlagResult::from_response(status, &response_text).ok_or(DreamhackError::Status {
+                    status,
+                    body: response_text,
+                })
+            }
+
+            /// Reflects a flag verdict in the local flags without re-requesting the list.
+            pub fn apply_flag_result(&mut self, result: FlagResult) {
+                match result {
+                    FlagResult::Correct | FlagResult::AlreadySolved => {
+                        self.metadata.flags.is_completed = true;
+                        self.metadata.flags.is_attempted = true;
+                    }
+                    FlagResult::Wrong => self.metadata.flags.is_attempted = true,
+                    FlagResult::RateLimited | FlagResult::NotLoggedIn => {}
+                }
+            }
+        }
+
+        /// Verdict of a flag submission.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum FlagResult {
+            Correct,
+            Wrong,
+            AlreadySolved,
+            RateLimited,
+            NotLoggedIn,
+        }
+
+        impl FlagResult {
+            /// Dreamhack answers a wrong flag with 400. An already solved challenge is
+            /// only recognised from the message body (English or Korean) of a success or
+            /// conflict response, so error messages that mention it are not misread.
+            fn from_response(status: StatusCode, body: &str) -> Option<Self> {
+                let already_solved = || {
+                    let body = body.to_lowercase();
+                    body.contains("already") || body.contains("이미")
+                };
+
+                match status {
+                    StatusCode::TOO_MANY_REQUESTS => Some(FlagResult::RateLimited),
+                    StatusCode::BAD_REQUEST => Some(FlagResult::Wrong),
+                    StatusCode::CONFLICT if already_solved() => Some(FlagResult::AlreadySolved),
+                    status if status.is_success() => Some(if already_solved() {
+                        FlagResult::AlreadySolved
+                    } else {
+                        FlagResult::Correct
+                    }),
+                    _ => None,
+                }
+            }
+        }
+
+        impl std::fmt::Display for FlagResult {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match *self {
+                    FlagResult::Correct => write!(f, "Correct!"),
+                    FlagResult::Wrong => write!(f, "Wrong flag"),
+                    FlagResult::AlreadySolved => write!(f, "Already solved"),
+                    FlagResult::RateLimited => write!(f, "Too many attempts, try again later"),
+                    FlagResult::NotLoggedIn => write!(f, "Not logged in"),
                 }
+            }
+        }
 
-                status.is_success()
+        impl ToColorString for FlagResult {
+            fn to_color_string(&self) -> Span<'_> {
+                let color = match self {
+                    FlagResult::Correct => Color::LightGreen,
+                    FlagResult::AlreadySolved => Color::Green,
+                    FlagResult::Wrong => Color::LightRed,
+                    FlagResult::RateLimited => Color::Yellow,
+                    FlagResult::NotLoggedIn => Color::Red,
+                };
+
+                Span::styled(self.to_string(), Style::default().fg(color).bold())
             }
         }
 
@@ -648,8 +1296,13 @@ pub mod challenge {
                 Difficulty::from(self.difficulty)
             }
 
-            /// author info
+            /// author nickname
             pub fn get_author(&self) -> &str {
+                self.author.get_nickname()
+            }
+
+            /// author info (level, rank, ...)
+            pub fn get_author_profile(&self) -> &Author {
                 &self.author
             }
 
@@ -659,11 +1312,11 @@ pub mod challenge {
             }
         }
         pub trait ToSimpleInfo {
-            fn to_simple_info(&self) -> Text;
+            fn to_simple_info(&self) -> Text<'_>;
         }
 
         impl ToSimpleInfo for Challenge {
-            fn to_simple_info(&self) -> Text {
+            fn to_simple_info(&self) -> Text<'_> {
                 let info = self.get_info();
                 let content = vec![
                     Line::raw(format!("Title: {}\n", info.get_title())),
@@ -680,12 +1333,17 @@ pub mod challenge {
         }
 
         pub trait ToDetailedInfo {
-            fn to_detailed_info(&self) -> Text;
+            fn to_detailed_info(&self) -> Text<'_>;
         }
 
         impl ToDetailedInfo for Challenge {
-            fn to_detailed_info(&self) -> Text {
+            fn to_detailed_info(&self) -> Text<'_> {
                 let info = self.get_info();
+                let author = info.get_author_profile();
+                let stats = self.metadata.get_stats();
+                let date = |time: Option<DateTime<FixedOffset>>| {
+                    time.map_or("-".to_string(), |time| time.format("%Y-%m-%d").to_string())
+                };
 
                 let content = vec![
                     Line::raw(format!("Title: {}\n", info.get_title())),
@@ -693,8 +1351,35 @@ pub mod challenge {
                         "Level: {}\n",
                         info.get_difficulty().to_color_string()
                     )),
-                    Line::raw(format!("Author: {}\n", info.get_author())),
-                    Line::raw(format!("Description: {}\n", info.get_description())),
+                    Line::raw(format!(
+                        "Author: {} (Lv.{}{})\n",
+                        author.get_nickname(),
+                        author.get_level(),
+                        if author.is_staff() { ", staff" } else { "" }
+                    )),
+                    Line::raw(format!(
+                        "Solvers: {}  Votes: {}  Writeups: {}{}\n",
+                        stats.solvers,
+                        stats.votes,
+                        stats.writeups,
+                        if self.metadata.flags.has_author_writeup() {
+                            " (author writeup)"
+                        } else {
+                            ""
+                        }
+                    )),
+                    Line::raw(format!(
+                        "Comments: {}  Questions: {}  Hits: {}\n",
+                        stats.comments, stats.questions, stats.hits
+                    )),
+                    Line::raw(format!(
+                        "Public: {} ({})  Created: {}\n",
+                        date(self.metadata.get_public_at()),
+                        self.metadata
+                            .get_age()
+                            .map_or("-".to_string(), |age| format!("{} ago", format_age(age))),
+                        date(self.metadata.get_created_at()),
+                    )),
                     Line::raw(format!("Tags: {:?}\n", info.get_tags())),
                     Line::raw("\n"),
                 ];
@@ -703,6 +1388,17 @@ pub mod challenge {
             }
         }
 
+        /// Compact age, e.g. "12m", "5d", "3mo", "2y".
+        pub fn format_age(age: chrono::Duration) -> String {
+            match age.num_days() {
+                _ if age.num_hours() < 1 => format!("{}m", age.num_minutes().max(0)),
+                days if days < 1 => format!("{}h", age.num_hours()),
+                days if days < 30 => format!("{}d", days),
+                days if days < 365 => format!("{}mo", days / 30),
+                days => format!("{}y", days / 365),
+            }
+        }
+
         impl ChallengeMetadata {
             /// repository name is title of challenge(whitespace replaced with '_')
             pub fn get_repository(&self) -> &str {
@@ -713,6 +1409,64 @@ pub mod challenge {
             pub fn get_public(&self) -> &str {
                 &self.public
             }
+
+            pub fn get_flags(&self) -> &Flags {
+                &self.flags
+            }
+
+            pub fn get_stats(&self) -> &ChallengeStats {
+                &self.stats
+            }
+
+            pub fn get_created_at(&self) -> Option<DateTime<FixedOffset>> {
+                DateTime::parse_from_rfc3339(&self.created_at).ok()
+            }
+
+            pub fn get_public_at(&self) -> Option<DateTime<FixedOffset>> {
+                DateTime::parse_from_rfc3339(&self.public_at).ok()
+            }
+
+            /// Deployment date as the API sends it, which is not always a timestamp.
+            pub fn get_deployed(&self) -> &str {
+                &self.deployed
+            }
+
+            /// Time since the challenge was made public (created, if never published).
+            pub fn get_age(&self) -> Option<chrono::Duration> {
+                self.get_public_at()
+                    .or_else(|| self.get_created_at())
+                    .map(|time| Utc::now() - time.with_timezone(&Utc))
+            }
+        }
+
+        impl Flags {
+            pub fn is_completed(&self) -> bool {
+                self.is_completed
+            }
+
+            pub fn is_attempted(&self) -> bool {
+                self.is_attempted
+            }
+
+            pub fn is_beginner(&self) -> bool {
+                self.is_beginner
+            }
+
+            pub fn needs_vm(&self) -> bool {
+                self.needs_vm
+            }
+
+            pub fn is_official(&self) -> bool {
+                self.official
+            }
+
+            pub fn has_author_writeup(&self) -> bool {
+                self.has_author_writeup
+            }
+
+            pub fn is_featured(&self) -> bool {
+                self.is_featured
+            }
         }
 
         impl From<ChallengeResponseData> for Challenge {
@@ -724,11 +1478,22 @@ pub mod challenge {
                         description: challenge.description,
                         difficulty: challenge.difficulty,
                         tags: challenge.tags,
-                        author: challenge.author.nickname,
+                        author: challenge.author,
                     },
                     metadata: ChallengeMetadata {
                         repository: challenge.repository,
                         public: challenge.public,
+                        created_at: challenge.created_at,
+                        public_at: challenge.public_at,
+                        deployed: challenge.deployed,
+                        stats: ChallengeStats {
+                            solvers: challenge.cnt_solvers,
+                            writeups: challenge.cnt_writeups,
+                            votes: challenge.cnt_vote,
+                            questions: challenge.cnt_questions,
+                            comments: challenge.cnt_comments,
+                            hits: challenge.hit_count.hits,
+                        },
                         flags: Flags {
                             is_completed: challenge.is_completed,
                             is_attempted: challenge.is_attempted,
@@ -748,8 +1513,13 @@ pub mod challenge {
 }
 
 pub mod vm_info {
-    use anyhow::Context;
+    use std::{fmt::Display, time::Duration};
+
+    use chrono::{DateTime, FixedOffset, Utc};
     use serde::{Deserialize, Serialize};
+    use tokio::{net::TcpStream, time::timeout};
+
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
 
     #[derive(Serialize, Deserialize, Default, Debug)]
     pub struct MachineInfo {
@@ -772,35 +1542,48 @@ pub mod vm_info {
     }
 
     impl PortMapping {
-        pub fn get_port(&self) -> u16 {
+        /// Port number, numeric strings (e.g. `"8080"`) are accepted as well.
+        pub fn get_port(&self) -> Option<u16> {
             match self {
-                PortMapping::Integer(port) => *port as u16,
-                _ => unreachable!(),
+                PortMapping::Integer(port) => u16::try_from(*port).ok(),
+                PortMapping::String(value) => value.trim().parse::<u16>().ok(),
             }
         }
 
-        pub fn get_protocol(&self) -> String {
+        pub fn get_protocol(&self) -> Option<Protocol> {
             match self {
-                PortMapping::String(protocol) => protocol.clone(),
-                _ => unreachable!(),
+                PortMapping::String(protocol) if self.get_port().is_none() => {
+                    Some(Protocol::from_str(protocol))
+                }
+                _ => None,
             }
         }
     }
 
-    #[derive(Debug, Clone, Default)]
+    #[derive(Debug, Clone, Default, PartialEq)]
     pub enum Protocol {
         #[default]
-        None,
         Tcp,
         Udp,
+        Other(String),
     }
 
     impl Protocol {
         fn from_str(protocol: &str) -> Self {
-            match protocol {
+            match protocol.trim().to_ascii_lowercase().as_str() {
                 "tcp" => Protocol::Tcp,
                 "udp" => Protocol::Udp,
-                _ => unreachable!(),
+                protocol => Protocol::Other(protocol.to_string()),
+            }
+        }
+    }
+
+    impl Display for Protocol {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Protocol::Tcp => write!(f, "TCP"),
+                Protocol::Udp => write!(f, "UDP"),
+                Protocol::Other(protocol) => write!(f, "{}", protocol.to_uppercase()),
             }
         }
     }
@@ -814,35 +1597,78 @@ pub mod vm_info {
     }
 
     impl MachineInfo {
+        pub fn get_state(&self) -> &str {
+            &self.state
+        }
+
+        pub fn is_running(&self) -> bool {
+            self.state.eq_ignore_ascii_case("running")
+        }
+
+        pub fn get_starttime(&self) -> Option<DateTime<FixedOffset>> {
+            DateTime::parse_from_rfc3339(&self.starttime).ok()
+        }
+
+        pub fn get_endtime(&self) -> Option<DateTime<FixedOffset>> {
+            DateTime::parse_from_rfc3339(&self.endtime).ok()
+        }
+
+        /// Time left until the VM is shut down, zero once expired.
+        pub fn get_remaining(&self) -> Option<chrono::Duration> {
+            self.get_endtime().map(|endtime| {
+                (endtime.with_timezone(&Utc) - Utc::now()).max(chrono::Duration::zero())
+            })
+        }
+
+        /// Whether every exposed TCP port accepts connections.
+        pub async fn is_reachable(&self) -> bool {
+            let network_info = self.get_network_info();
+            if network_info.is_empty() {
+                return false;
+            }
+
+            for info in network_info
+                .iter()
+                .filter(|info| info.protocol == Protocol::Tcp)
+            {
+                let connect = TcpStream::connect((info.host.as_str(), info.external));
+                if !matches!(timeout(CONNECT_TIMEOUT, connect).await, Ok(Ok(_))) {
+                    return false;
+                }
+            }
+            true
+        }
+
         /// port mappings of machine.
         ///
-        /// example: "port_mappings":[["tcp",10332,8080]]
-        pub fn get_network_info(&self) -> Option<NetworkInfo> {
-            let info = self
-                .port_mappings
-                .first()
-                .context("Failed to get port mappings")
-                .ok();
-            info.map(|info| NetworkInfo {
-                protocol: Protocol::from_str(
-                    &info
-                        .first()
-                        .context("Failed to get protocol")
-                        .unwrap()
-                        .get_protocol(),
-                ),
-                host: self.host.clone(),
-                external: info
-                    .get(1)
-                    .context("Failed to get external port")
-                    .unwrap()
-                    .get_port(),
-                internal: info
-                    .get(2)
-                    .context("Failed to get internal port")
-                    .unwrap()
-                    .get_port(),
-            })
+        /// example: "port_mappings":[["tcp",10332,8080],["tcp",10333,22]]
+        ///
+        /// The protocol defaults to TCP when missing, and malformed mappings are skipped.
+        pub fn get_network_info(&self) -> Vec<NetworkInfo> {
+            self.port_mappings
+                .iter()
+                .filter_map(|mapping| {
+                    let protocol = mapping
+                        .iter()
+                        .find_map(PortMapping::get_protocol)
+                        .unwrap_or_default();
+                    let mut ports = mapping.iter().filter_map(PortMapping::get_port);
+
+                    match (ports.next(), ports.next()) {
+                        (Some(external), internal) => Some(NetworkInfo {
+                            protocol,
+                            host: self.host.clone(),
+                            external,
+                            internal: internal.unwrap_or(external),
+                        }),
+                        _ => {
+                            #[cfg(debug_assertions)]
+                            log::warn!("Skipping malformed port mapping: {:?}", mapping);
+                            None
+                        }
+                    }
+                })
+                .collect()
         }
     }
 
@@ -854,5 +1680,14 @@ pub mod vm_info {
         pub fn get_uri_web(&self) -> String {
             format!("http://{}:{}/", self.host, self.external)
         }
+
+        /// Netcat command line, `None` for protocols netcat does not speak.
+        pub fn get_nc_command(&self) -> Option<String> {
+            match self.protocol {
+                Protocol::Tcp => Some(format!("nc {} {}", self.host, self.external)),
+                Protocol::Udp => Some(format!("nc -u {} {}", self.host, self.external)),
+                Protocol::Other(_) => None,
+            }
+        }
     }
 }
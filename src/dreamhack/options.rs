@@ -40,11 +40,12 @@ impl From<u64> for Difficulty {
     }
 }
 
+/// Beginner is the beginner track (see `Scope`), not a level.
 impl From<Difficulty> for Option<u64> {
     fn from(value: Difficulty) -> Self {
         match value {
-            Difficulty::All => None,
-            Difficulty::LEVEL1 | Difficulty::Beginner => Some(1),
+            Difficulty::All | Difficulty::Beginner => None,
+            Difficulty::LEVEL1 => Some(1),
             Difficulty::LEVEL2 => Some(2),
             Difficulty::LEVEL3 => Some(3),
             Difficulty::LEVEL4 => Some(4),
@@ -63,6 +64,7 @@ impl PopupItem for Difficulty {
     fn variants() -> Vec<Difficulty> {
         [
             Difficulty::All,
+            Difficulty::Beginner,
             Difficulty::LEVEL1,
             Difficulty::LEVEL2,
             Difficulty::LEVEL3,
@@ -80,23 +82,24 @@ impl PopupItem for Difficulty {
     fn from_index(index: usize) -> Difficulty {
         match index {
             0 => Difficulty::All,
-            1 => Difficulty::LEVEL1,
-            2 => Difficulty::LEVEL2,
-            3 => Difficulty::LEVEL3,
-            4 => Difficulty::LEVEL4,
-            5 => Difficulty::LEVEL5,
-            6 => Difficulty::LEVEL6,
-            7 => Difficulty::LEVEL7,
-            8 => Difficulty::LEVEL8,
-            9 => Difficulty::LEVEL9,
-            10 => Difficulty::LEVEL10,
+            1 => Difficulty::Beginner,
+            2 => Difficulty::LEVEL1,
+            3 => Difficulty::LEVEL2,
+            4 => Difficulty::LEVEL3,
+            5 => Difficulty::LEVEL4,
+            6 => Difficulty::LEVEL5,
+            7 => Difficulty::LEVEL6,
+            8 => Difficulty::LEVEL7,
+            9 => Difficulty::LEVEL8,
+            10 => Difficulty::LEVEL9,
+            11 => Difficulty::LEVEL10,
             _ => Difficulty::All,
         }
     }
 }
 
 impl ToColorString for Difficulty {
-    fn to_color_string(&self) -> Span {
+    fn to_color_string(&self) -> Span<'_> {
         match self {
             Difficulty::LEVEL1 => Span::styled(
                 "LEVEL 1",
@@ -168,7 +171,8 @@ impl ToRequestString for Difficulty {
             Difficulty::LEVEL9 => "9".to_string(),
             Difficulty::LEVEL10 => "10".to_string(),
             Difficulty::Unranked => "0".to_string(),
-            Difficulty::Beginner => "1".to_string(),
+            // Not a level, sent as the beginner scope instead
+            Difficulty::Beginner => "".to_string(),
             Difficulty::All => "".to_string(),
         }
     }
@@ -196,47 +200,98 @@ impl Display for Difficulty {
 
 use super::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+/// Category of a challenge, named like the API does (e.g. "pwnable").
+///
+/// Dreamhack adds categories over time, so any name is accepted (e.g. by `cat:`
+/// in the search box), not only the ones of `KNOWN_CATEGORIES`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Category {
     All,
-    Pwnable,
-    Reversing,
-    Web,
-    Crypto,
+    Named(String),
 }
 
-impl PopupItem for Category {
-    fn from_index(index: usize) -> Category {
-        match index {
-            0 => Category::All,
-            1 => Category::Pwnable,
-            2 => Category::Reversing,
-            3 => Category::Web,
-            4 => Category::Crypto,
-            _ => Category::All,
+/// Categories with a display name, in the order Dreamhack lists them.
+///
+/// The API has no list of categories, so the Category popup starts with these and
+/// adds the ones found in the challenges it loads.
+const KNOWN_CATEGORIES: [(&str, &str); 7] = [
+    ("pwnable", "Pwnable"),
+    ("reversing", "Reversing"),
+    ("web", "Web"),
+    ("crypto", "Crypto"),
+    ("misc", "Misc"),
+    ("forensics", "Forensics"),
+    ("web3", "Web3"),
+];
+
+impl Category {
+    /// `""` and `"all"` are every category.
+    pub fn new(name: &str) -> Self {
+        match name.trim().to_lowercase() {
+            name if name.is_empty() || name == "all" => Category::All,
+            name => Category::Named(name),
         }
     }
 
-    fn variants() -> Vec<Category> {
-        [
-            Category::All,
-            Category::Pwnable,
-            Category::Reversing,
-            Category::Web,
-            Category::Crypto,
-        ]
-        .to_vec()
+    /// All and the known categories, the Category popup before any challenge is loaded.
+    pub fn defaults() -> Vec<Category> {
+        std::iter::once(Category::All)
+            .chain(KNOWN_CATEGORIES.iter().map(|(name, _)| Category::new(name)))
+            .collect()
+    }
+
+    fn known_index(&self) -> Option<usize> {
+        match self {
+            Category::All => None,
+            Category::Named(name) => KNOWN_CATEGORIES.iter().position(|(known, _)| known == name),
+        }
+    }
+
+    /// All first, then the known categories in Dreamhack's order, then the rest by name.
+    pub fn sort_key(&self) -> (usize, String) {
+        match self {
+            Category::All => (0, String::new()),
+            Category::Named(name) => (
+                self.known_index().map_or(usize::MAX, |i| i + 1),
+                name.clone(),
+            ),
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = std::convert::Infallible;
+
+    /// Known categories match by a unique prefix of their name (`pwn`), any other
+    /// name is taken as is.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        if value.is_empty() || KNOWN_CATEGORIES.iter().any(|(name, _)| *name == value) {
+            return Ok(Category::new(&value));
+        }
+
+        let mut candidates = KNOWN_CATEGORIES
+            .iter()
+            .filter(|(name, _)| name.starts_with(&value));
+        Ok(match (candidates.next(), candidates.next()) {
+            (Some((name, _)), None) => Category::new(name),
+            _ => Category::new(&value),
+        })
     }
 }
 
 impl Display for Category {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
+        match self {
             Category::All => write!(f, "All"),
-            Category::Pwnable => write!(f, "Pwnable"),
-            Category::Reversing => write!(f, "Reversing"),
-            Category::Web => write!(f, "Web"),
-            Category::Crypto => write!(f, "Crypto"),
+            Category::Named(name) => match self.known_index() {
+                Some(i) => write!(f, "{}", KNOWN_CATEGORIES[i].1),
+                None => {
+                    let mut chars = name.chars();
+                    let first = chars.next().map(|c| c.to_uppercase().to_string());
+                    write!(f, "{}{}", first.unwrap_or_default(), chars.as_str())
+                }
+            },
         }
     }
 }
@@ -245,10 +300,7 @@ impl ToRequestString for Category {
     fn to_request_string(&self) -> String {
         match self {
             Category::All => "".to_string(),
-            Category::Pwnable => "pwnable".to_string(),
-            Category::Reversing => "reversing".to_string(),
-            Category::Web => "web".to_string(),
-            Category::Crypto => "crypto".to_string(),
+            Category::Named(name) => name.clone(),
         }
     }
 }
@@ -345,3 +397,90 @@ impl Display for Orderings {
         }
     }
 }
+
+/// Who made the challenges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeType {
+    All,
+    Official,
+    Community,
+}
+
+impl PopupItem for ChallengeType {
+    fn from_index(index: usize) -> ChallengeType {
+        match index {
+            0 => ChallengeType::All,
+            1 => ChallengeType::Official,
+            2 => ChallengeType::Community,
+            _ => ChallengeType::All,
+        }
+    }
+
+    fn variants() -> Vec<ChallengeType> {
+        [
+            ChallengeType::All,
+            ChallengeType::Official,
+            ChallengeType::Community,
+        ]
+        .to_vec()
+    }
+}
+
+impl ToRequestString for ChallengeType {
+    fn to_request_string(&self) -> String {
+        match self {
+            ChallengeType::All => "".to_string(),
+            ChallengeType::Official => "official".to_string(),
+            ChallengeType::Community => "community".to_string(),
+        }
+    }
+}
+
+impl Display for ChallengeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ChallengeType::All => write!(f, "All"),
+            ChallengeType::Official => write!(f, "Official"),
+            ChallengeType::Community => write!(f, "Community"),
+        }
+    }
+}
+
+/// Track the challenges belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    Beginner,
+}
+
+impl PopupItem for Scope {
+    fn from_index(index: usize) -> Scope {
+        match index {
+            0 => Scope::All,
+            1 => Scope::Beginner,
+            _ => Scope::All,
+        }
+    }
+
+    fn variants() -> Vec<Scope> {
+        [Scope::All, Scope::Beginner].to_vec()
+    }
+}
+
+impl ToRequestString for Scope {
+    fn to_request_string(&self) -> String {
+        match self {
+            Scope::All => "".to_string(),
+            Scope::Beginner => "beginner".to_string(),
+        }
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Scope::All => write!(f, "All"),
+            Scope::Beginner => write!(f, "Beginner Track"),
+        }
+    }
+}
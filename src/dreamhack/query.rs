@@ -0,0 +1,513 @@
+//! Filter query language of the search box and `creamhack search`.
+//!
+//! ```text
+//! heap cat:pwn level:3..5 status:todo tag:"use after free" solvers:<200 sort:-solvers
+//! ```
+//!
+//! Words without a `key:` (or in quotes) are the free-text search. Category, a single level,
+//! status, type, scope and sort are sent to the API; level ranges, tags, solvers and votes
+//! are filtered on the client, one page at a time.
+
+use std::{fmt::Display, ops::Range};
+
+use super::{
+    challenge::{handle::Challenge, RequestChallengeList},
+    options::{Category, ChallengeType, Difficulty, Orderings, Scope, Status},
+    ToRequestString,
+};
+use crate::custom_widgets::popup::PopupItem;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    text: String,
+    category: Option<Category>,
+    level: Option<NumberRange>,
+    status: Option<Status>,
+    ordering: Option<Orderings>,
+    type_value: Option<ChallengeType>,
+    scope: Option<Scope>,
+    tags: Vec<String>,
+    solvers: Option<NumberRange>,
+    votes: Option<NumberRange>,
+}
+
+/// Inclusive bounds of `3`, `3..5`, `..5`, `3..`, `<200`, `>=10`, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberRange {
+    min: u64,
+    max: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    /// Byte range of the offending term in the input
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl NumberRange {
+    /// `min` and `max` are swapped when out of order.
+    pub fn new(min: u64, max: u64) -> Self {
+        NumberRange {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    pub fn get_min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+
+    /// The single value of `3` or `3..3`.
+    pub fn exact(&self) -> Option<u64> {
+        (self.min == self.max).then_some(self.min)
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let number = |s: &str| s.trim().parse::<u64>().ok();
+
+        if let Some((min, max)) = value.split_once("..") {
+            let min = if min.is_empty() { 0 } else { number(min)? };
+            let max = if max.is_empty() {
+                u64::MAX
+            } else {
+                number(max)?
+            };
+            return (min <= max).then_some(NumberRange { min, max });
+        }
+
+        let range = if let Some(value) = value.strip_prefix("<=") {
+            NumberRange {
+                min: 0,
+                max: number(value)?,
+            }
+        } else if let Some(value) = value.strip_prefix(">=") {
+            NumberRange {
+                min: number(value)?,
+                max: u64::MAX,
+            }
+        } else if let Some(value) = value.strip_prefix('<') {
+            NumberRange {
+                min: 0,
+                max: number(value)?.checked_sub(1)?,
+            }
+        } else if let Some(value) = value.strip_prefix('>') {
+            NumberRange {
+                min: number(value)?.checked_add(1)?,
+                max: u64::MAX,
+            }
+        } else {
+            let value = number(value.strip_prefix('=').unwrap_or(value))?;
+            NumberRange {
+                min: value,
+                max: value,
+            }
+        };
+        Some(range)
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let mut query = SearchQuery::default();
+        let mut text = Vec::new();
+
+        for (term, span) in split_terms(input)? {
+            // Quoted terms are always text, e.g. "flag:format"
+            let quoted = input[span.clone()].starts_with('"');
+            match term.split_once(':').filter(|_| !quoted) {
+                Some((key, value)) => {
+                    query
+                        .set_filter(key, value)
+                        .map_err(|message| QueryError { span, message })?;
+                }
+                None => text.push(term),
+            }
+        }
+
+        query.text = text.join(" ");
+        Ok(query)
+    }
+
+    fn set_filter(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return Err(format!("`{}:` needs a value", key));
+        }
+
+        match key.to_lowercase().as_str() {
+            // Categories the client does not know yet are sent as is
+            "cat" | "category" => self.category = value.parse::<Category>().ok(),
+            "level" | "lv" | "difficulty" => {
+                let level = NumberRange::parse(value)
+                    .filter(|range| range.max <= 10 || range.max == u64::MAX);
+                self.level = Some(level.ok_or_else(|| {
+                    format!("Invalid level `{}`, expected e.g. 3, 3..5 or >=7", value)
+                })?);
+            }
+            "status" | "is" => {
+                self.status = Some(parse_option(value).ok_or_else(|| {
+                    format!("Unknown status `{}`, expected {}", value, names::<Status>())
+                })?);
+            }
+            "sort" | "order" => {
+                self.ordering = Some(match value.to_lowercase().as_str() {
+                    "new" | "newest" => Orderings::Newist,
+                    "-solvers" => Orderings::MostSolved,
+                    "solvers" => Orderings::LeastSolved,
+                    _ => {
+                        return Err(format!(
+                            "Unknown sort `{}`, expected new, -solvers or solvers",
+                            value
+                        ))
+                    }
+                });
+            }
+            "type" => {
+                self.type_value = Some(parse_option(value).ok_or_else(|| {
+                    format!(
+                        "Unknown type `{}`, expected {}",
+                        value,
+                        names::<ChallengeType>()
+                    )
+                })?);
+            }
+            "scope" => {
+                self.scope = Some(parse_option(value).ok_or_else(|| {
+                    format!("Unknown scope `{}`, expected {}", value, names::<Scope>())
+                })?);
+            }
+            "tag" => self.tags.push(value.to_lowercase()),
+            "solvers" => {
+                self.solvers = Some(NumberRange::parse(value).ok_or_else(|| {
+                    format!("Invalid solvers `{}`, expected e.g. <200 or 10..50", value)
+                })?);
+            }
+            "votes" => {
+                self.votes = Some(
+                    NumberRange::parse(value)
+                        .ok_or_else(|| format!("Invalid votes `{}`, expected e.g. >=5", value))?,
+                );
+            }
+            _ => {
+                return Err(format!(
+                "Unknown filter `{}:`, expected cat, level, status, type, scope, tag, solvers, votes or sort",
+                key
+            ))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_category(&self) -> Option<&Category> {
+        self.category.as_ref()
+    }
+
+    /// Sets the options the API can filter by, the rest is left to `matches`.
+    ///
+    /// Options of the query override the ones already set on `request`.
+    pub fn apply(&self, request: &mut RequestChallengeList) {
+        request.set_search(self.text.clone());
+        if let Some(category) = &self.category {
+            request.set_category(category.clone());
+        }
+        if let Some(level) = self.level.and_then(|level| level.exact()) {
+            request.set_difficulty(Difficulty::from(level));
+        }
+        if let Some(status) = self.status {
+            request.set_status(status);
+        }
+        if let Some(ordering) = self.ordering {
+            request.set_ordering(ordering);
+        }
+        if let Some(type_value) = self.type_value {
+            request.set_type(type_value);
+        }
+        if let Some(scope) = self.scope {
+            request.set_scope(scope);
+        }
+    }
+
+    /// Filters the API can not apply.
+    pub fn matches(&self, challenge: &Challenge) -> bool {
+        let info = challenge.get_info();
+        let stats = challenge.get_metadata().get_stats();
+
+        let level = match (self.level, Option::<u64>::from(info.get_difficulty())) {
+            (Some(range), Some(level)) => range.contains(level),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let tags = self.tags.iter().all(|wanted| {
+            info.get_tags()
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(wanted.as_str()))
+        });
+        let solvers = self
+            .solvers
+            .is_none_or(|range| range.contains(stats.solvers));
+        let votes = self.votes.is_none_or(|range| range.contains(stats.votes));
+
+        level && tags && solvers && votes
+    }
+}
+
+/// Splits on whitespace outside of double quotes, quotes are removed.
+fn split_terms(input: &str) -> Result<Vec<(String, Range<usize>)>, QueryError> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut start = None;
+    let mut quote_start = None;
+
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => {
+                start.get_or_insert(i);
+                quote_start = match quote_start {
+                    Some(_) => None,
+                    None => Some(i),
+                };
+            }
+            c if c.is_whitespace() && quote_start.is_none() => {
+                if let Some(start) = start.take() {
+                    terms.push((std::mem::take(&mut term), start..i));
+                }
+            }
+            c => {
+                start.get_or_insert(i);
+                term.push(c);
+            }
+        }
+    }
+
+    if let Some(quote_start) = quote_start {
+        return Err(QueryError {
+            span: quote_start..input.len(),
+            message: "Unclosed quote".to_string(),
+        });
+    }
+    if let Some(start) = start {
+        terms.push((term, start..input.len()));
+    }
+    Ok(terms)
+}
+
+/// Matches a prefix of the name or API value, e.g. `pwn` for Pwnable.
+fn parse_option<T: PopupItem + Display + ToRequestString>(value: &str) -> Option<T> {
+    let value = value.to_lowercase();
+    let mut candidates = T::variants().into_iter().filter(|item| {
+        item.to_string()
+            .to_lowercase()
+            .replace(' ', "")
+            .starts_with(&value)
+            || item.to_request_string().starts_with(&value)
+    });
+
+    // Ambiguous prefixes are rejected
+    match (candidates.next(), candidates.next()) {
+        (Some(item), None) => Some(item),
+        _ => None,
+    }
+}
+
+fn names<T: PopupItem + Display>() -> String {
+    T::variants()
+        .iter()
+        .map(|item| item.to_string().to_lowercase().replace(' ', ""))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(input: &str) -> QueryError {
+        SearchQuery::parse(input).expect_err(input)
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let range = |value: &str| NumberRange::parse(value);
+
+        assert_eq!(range("<200"), Some(NumberRange { min: 0, max: 199 }));
+        assert_eq!(range("<=200"), Some(NumberRange { min: 0, max: 200 }));
+        assert_eq!(
+            range(">7"),
+            Some(NumberRange {
+                min: 8,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(
+            range(">=7"),
+            Some(NumberRange {
+                min: 7,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(range("3..5"), Some(NumberRange { min: 3, max: 5 }));
+        assert_eq!(range("..5"), Some(NumberRange { min: 0, max: 5 }));
+        assert_eq!(
+            range("3.."),
+            Some(NumberRange {
+                min: 3,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(range("=4"), Some(NumberRange { min: 4, max: 4 }));
+        assert_eq!(range("4").and_then(|range| range.exact()), Some(4));
+
+        assert_eq!(range("5..3"), None);
+        assert_eq!(range("<0"), None);
+        assert_eq!(range("abc"), None);
+        assert_eq!(range(">=x"), None);
+    }
+
+    #[test]
+    fn new_range_swaps_bounds() {
+        assert_eq!(NumberRange::new(5, 3), NumberRange { min: 3, max: 5 });
+    }
+
+    #[test]
+    fn parses_filters_and_text() {
+        let query =
+            SearchQuery::parse("heap cat:pwn level:3..5 status:todo sort:-solvers overflow")
+                .unwrap();
+
+        assert_eq!(query.text, "heap overflow");
+        assert_eq!(query.category, Some(Category::new("pwnable")));
+        assert_eq!(query.level, Some(NumberRange { min: 3, max: 5 }));
+        assert_eq!(query.status, Some(Status::ToDo));
+        assert_eq!(query.ordering, Some(Orderings::MostSolved));
+    }
+
+    #[test]
+    fn parses_level_and_solvers_ranges() {
+        let query = SearchQuery::parse("level:>=7 solvers:<200 votes:10..").unwrap();
+
+        assert_eq!(
+            query.level,
+            Some(NumberRange {
+                min: 7,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(query.solvers, Some(NumberRange { min: 0, max: 199 }));
+        assert_eq!(
+            query.votes,
+            Some(NumberRange {
+                min: 10,
+                max: u64::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_levels_above_ten() {
+        let error = parse_error("level:3..11");
+        assert!(error.message.contains("Invalid level"), "{}", error);
+        assert_eq!(error.span, 0..11);
+    }
+
+    #[test]
+    fn keeps_quoted_values_together() {
+        let query = SearchQuery::parse(r#"tag:"use after free" "two words""#).unwrap();
+
+        assert_eq!(query.tags, vec!["use after free".to_string()]);
+        assert_eq!(query.text, "two words");
+    }
+
+    #[test]
+    fn quoted_terms_are_text() {
+        let query = SearchQuery::parse(r#""flag:format" heap"#).unwrap();
+
+        assert_eq!(query.text, "flag:format heap");
+        assert_eq!(query.tags, Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let error = parse_error("heap foo:bar");
+
+        assert!(
+            error.message.starts_with("Unknown filter `foo:`"),
+            "{}",
+            error
+        );
+        assert_eq!(error.span, 5..12);
+    }
+
+    #[test]
+    fn matches_unique_prefixes_only() {
+        let query = SearchQuery::parse("status:att type:o scope:beg").unwrap();
+        assert_eq!(query.status, Some(Status::Attempted));
+        assert_eq!(query.type_value, Some(ChallengeType::Official));
+        assert_eq!(query.scope, Some(Scope::Beginner));
+
+        // All and Attempted
+        let error = parse_error("status:a");
+        assert!(error.message.starts_with("Unknown status `a`"), "{}", error);
+        assert_eq!(error.span, 0..8);
+    }
+
+    #[test]
+    fn rejects_empty_values() {
+        let error = parse_error("heap  cat:");
+
+        assert_eq!(error.message, "`cat:` needs a value");
+        assert_eq!(error.span, 6..10);
+    }
+
+    #[test]
+    fn reports_unclosed_quotes() {
+        let error = parse_error(r#"heap "use after"#);
+
+        assert_eq!(error.message, "Unclosed quote");
+        assert_eq!(error.span, 5..15);
+    }
+
+    #[test]
+    fn spans_are_byte_offsets() {
+        // `힙` is three bytes long
+        let error = parse_error("힙 sort:old");
+        assert_eq!(error.span, 4..12);
+    }
+
+    #[test]
+    fn empty_input_is_an_empty_query() {
+        assert_eq!(SearchQuery::parse("").unwrap(), SearchQuery::default());
+        assert_eq!(SearchQuery::parse("   ").unwrap(), SearchQuery::default());
+    }
+
+    #[test]
+    fn applies_an_exact_level_only() {
+        let mut request = RequestChallengeList::new();
+        SearchQuery::parse("heap level:3")
+            .unwrap()
+            .apply(&mut request);
+        assert_eq!(request.get_search(), "heap");
+        assert_eq!(request.get_difficulty(), Difficulty::LEVEL3);
+
+        let mut request = RequestChallengeList::new();
+        SearchQuery::parse("level:3..5")
+            .unwrap()
+            .apply(&mut request);
+        assert_eq!(request.get_difficulty(), Difficulty::All);
+    }
+}
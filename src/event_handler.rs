@@ -2,16 +2,50 @@
 /// But experimental features
 mod mouse {
     use crossterm::event::{MouseEvent, MouseEventKind};
-    use ratatui::layout::{Position, Rect};
+    use ratatui::layout::{Layout, Position, Rect};
 
-    use crate::{custom_widgets::button::ButtonState, render::WARGAME_BLOCK_SIZE, termui::*};
+    use crate::{
+        config::ListView, custom_widgets::button::ButtonState, render::WARGAME_BLOCK_SIZE,
+        termui::*,
+    };
 
     impl App {
-        fn get_current_selected_index(&self, event: MouseEvent, area: Rect) -> usize {
+        /// Index of the clicked challenge, `None` for the title/header rows or empty space.
+        fn get_current_selected_index(&self, event: MouseEvent, area: Rect) -> Option<usize> {
             let offset = self.ui_state.challenges.state.offset();
-            let index = (((event.row - area.y) as usize) / WARGAME_BLOCK_SIZE) + offset;
 
-            index
+            // The block title takes the first row, the table header the second one
+            let (header_rows, row_height) = match self.ui_state.list_view {
+                ListView::List => (1, WARGAME_BLOCK_SIZE),
+                ListView::Table => (2, 1),
+            };
+
+            ((event.row - area.y) as usize)
+                .checked_sub(header_rows)
+                .map(|row| row / row_height + offset)
+                .filter(|index| *index < self.ui_state.challenges.items.len())
+        }
+
+        /// Column of the table header at the clicked position.
+        fn get_table_column(&self, event: MouseEvent, area: Rect) -> Option<TableColumn> {
+            if self.ui_state.list_view != ListView::Table || event.row != area.y + 1 {
+                return None;
+            }
+
+            // Same layout as `Table`: the highlight symbol column, then columns spaced by 1
+            let columns_area = Rect {
+                x: area.x + 1,
+                width: area.width.saturating_sub(1),
+                ..area
+            };
+            let columns = Layout::horizontal(TableColumn::ALL.map(|column| column.width()))
+                .spacing(1)
+                .split(columns_area);
+
+            columns
+                .iter()
+                .position(|rect| (rect.x..rect.x + rect.width).contains(&event.column))
+                .map(|index| TableColumn::ALL[index])
         }
 
         pub(crate) fn handle_search_mouse_event(&mut self, event: MouseEvent, area: Rect) {
@@ -54,41 +88,9 @@ mod mouse {
 
                     self.ui_state.options.set_buttons_index(option_index);
 
-                    match option_index {
-                        0 => {
-                            #[cfg(debug_assertions)]
-                            log::info!("Mouse clicked on button {}", option_index);
-                            self.ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::CategoryPopup);
-                        }
-                        1 => {
-                            #[cfg(debug_assertions)]
-                            log::info!("Mouse clicked on button {}", option_index);
-                            self.ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::DifficultyPopup);
-                        }
-                        2 => {
-                            #[cfg(debug_assertions)]
-                            log::info!("Mouse clicked on button {}", option_index);
-                            self.ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::StatusPopup);
-                        }
-                        3 => {
-                            #[cfg(debug_assertions)]
-                            log::info!("Mouse clicked on button {}", option_index);
-                            self.ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::OrderPopup);
-                        }
-                        _ => {}
-                    }
+                    #[cfg(debug_assertions)]
+                    log::info!("Mouse clicked on button {}", option_index);
+                    self.ui_state.options.open_popup(option_index);
                     self.ui_state.popup_state = PopupState::Options
                 }
                 _ => {}
@@ -100,12 +102,13 @@ mod mouse {
                 return;
             }
 
+            let inside = area.contains(Position::new(event.column, event.row));
             match event.kind {
-                MouseEventKind::ScrollUp => {
+                MouseEventKind::ScrollUp if inside => {
                     self.ui_state.current_tab.set_tab(Tabs::WargameList);
                     self.ui_state.challenges.select_previous();
                 }
-                MouseEventKind::ScrollDown => {
+                MouseEventKind::ScrollDown if inside => {
                     self.ui_state.current_tab.set_tab(Tabs::WargameList);
                     self.ui_state.challenges.select_next();
                 }
@@ -122,15 +125,32 @@ mod mouse {
                         );
                     }
 
-                    //
-                    let index = self.get_current_selected_index(event, area);
-                    self.ui_state.challenges.state.select(Some(index));
-                    log::info!("Selected index: {}", index);
+                    if let Some(column) = self.get_table_column(event, area) {
+                        self.sort_by(column);
+                    } else if let Some(index) = self.get_current_selected_index(event, area) {
+                        self.ui_state.challenges.state.select(Some(index));
+                        #[cfg(debug_assertions)]
+                        log::info!("Selected index: {}", index);
+                    }
                 }
                 _ => {}
             }
         }
 
+        pub(crate) fn handle_details_mouse_event(&mut self, event: MouseEvent, area: Rect) {
+            if self.ui_state.popup_state != PopupState::None
+                || !area.contains(Position::new(event.column, event.row))
+            {
+                return;
+            }
+
+            match event.kind {
+                MouseEventKind::ScrollUp => self.scroll_details(-DETAILS_SCROLL_STEP),
+                MouseEventKind::ScrollDown => self.scroll_details(DETAILS_SCROLL_STEP),
+                _ => {}
+            }
+        }
+
         pub(crate) fn handle_enter_flag_mouse_event(&mut self, event: MouseEvent, area: Rect) {
             if self.ui_state.popup_state != PopupState::None {
                 return;
@@ -176,6 +196,16 @@ mod mouse {
                             log::info!("Mouse clicked on Create VM Button");
                             self.handle_create_vm();
                         }
+                        3 => {
+                            #[cfg(debug_assertions)]
+                            log::info!("Mouse clicked on Extend VM Button");
+                            self.handle_extend_vm();
+                        }
+                        4 => {
+                            #[cfg(debug_assertions)]
+                            log::info!("Mouse clicked on Stop VM Button");
+                            self.handle_stop_vm();
+                        }
                         _ => {}
                     }
                 }
@@ -186,10 +216,7 @@ mod mouse {
 }
 
 mod keyboard {
-    use std::{
-        ops::{AddAssign, SubAssign},
-        path::PathBuf,
-    };
+    use std::path::PathBuf;
 
     use anyhow::Context;
     use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
@@ -197,7 +224,7 @@ mod keyboard {
     use crate::{
         custom_widgets::button::ButtonState,
         fs_tree::build_tree,
-        termui::{App, CursorState, OptionsPopupState, PopupState, Tabs},
+        termui::{App, CursorState, PopupState, TableColumn, Tabs, DETAILS_PAGE_SIZE},
     };
 
     impl App {
@@ -206,6 +233,10 @@ mod keyboard {
                 return;
             }
 
+            // Errors stay in the footer until the next key press
+            self.ui_state.error = None;
+            self.ui_state.notice = None;
+
             match self.ui_state.popup_state {
                 PopupState::None => match self.ui_state.current_tab {
                     Tabs::Search => self.handle_search_input(key),
@@ -219,6 +250,8 @@ mod keyboard {
                 PopupState::Options => {
                     self.handle_options_input(key);
                 }
+                PopupState::Downloads => self.handle_downloads_popup_input(key),
+                PopupState::ArchivePreview => self.handle_archive_preview_input(key),
             }
 
             #[cfg(debug_assertions)]
@@ -230,10 +263,21 @@ mod keyboard {
             log::info!("Handle search input");
             self.ui_state.cursor_state = CursorState::Search;
             match key.code {
-                KeyCode::Char('q') => self.should_exit = true,
+                // Queries like `tag:sqli` need the letter
+                KeyCode::Char('q') if self.ui_state.search.input.is_empty() => {
+                    self.should_exit = true
+                }
                 KeyCode::Enter => self.start_search(),
-                KeyCode::Char(to_insert) => self.ui_state.search.enter_char(to_insert),
-                KeyCode::Backspace => self.ui_state.search.delete_char(),
+                KeyCode::Char(to_insert) => {
+                    self.ui_state.search.enter_char(to_insert);
+                    self.ui_state.query_error = None;
+                    self.mark_search_edited();
+                }
+                KeyCode::Backspace => {
+                    self.ui_state.search.delete_char();
+                    self.ui_state.query_error = None;
+                    self.mark_search_edited();
+                }
                 KeyCode::Left => self.ui_state.search.move_cursor_left(),
                 KeyCode::Right => self.ui_state.search.move_cursor_right(),
                 _ => {}
@@ -243,35 +287,20 @@ mod keyboard {
         fn handle_options_input(&mut self, key: KeyEvent) {
             let buttons_index = self.ui_state.options.get_buttons_index();
             if self.ui_state.popup_state == PopupState::Options {
-                let popup_items_len = self.get_popup_items_length();
-                let items = self.ui_state.options.get_mut_popup().get_mut_items();
                 #[cfg(debug_assertions)]
                 log::info!("Handle options popup input");
                 match key.code {
-                    KeyCode::Up => {
-                        if items[buttons_index].get_index() > 0 {
-                            items[buttons_index].get_index().sub_assign(1);
-                        }
-                    }
-                    KeyCode::Down => {
-                        if items[buttons_index].get_index() < popup_items_len - 1 {
-                            items[buttons_index].get_index().add_assign(1);
-                        }
-                    }
+                    KeyCode::Up => self.ui_state.options.move_popup_cursor(-1),
+                    KeyCode::Down => self.ui_state.options.move_popup_cursor(1),
+                    KeyCode::Char(' ') => self.ui_state.options.mark_popup_item(),
                     KeyCode::Enter => {
                         self.apply_popup_selection();
                         self.ui_state.popup_state = PopupState::None;
-                        self.ui_state
-                            .options
-                            .get_mut_popup()
-                            .set_state(OptionsPopupState::None);
+                        self.ui_state.options.close_popup();
                     }
                     KeyCode::Esc | KeyCode::Char('q') => {
                         self.ui_state.popup_state = PopupState::None;
-                        self.ui_state
-                            .options
-                            .get_mut_popup()
-                            .set_state(OptionsPopupState::None);
+                        self.ui_state.options.close_popup();
                     }
                     _ => {
                         #[cfg(debug_assertions)]
@@ -279,50 +308,23 @@ mod keyboard {
                     }
                 }
             } else {
-                let buttons = self.ui_state.options.get_mut_buttons();
+                let buttons_len = self.ui_state.options.get_buttons().len();
                 #[cfg(debug_assertions)]
                 log::info!("Handle options input");
 
+                let select = |app: &mut Self, index: usize| {
+                    app.ui_state.options.clear_button_state();
+                    app.ui_state.options.get_mut_buttons()[index].set_state(ButtonState::Selected);
+                    app.ui_state.options.set_buttons_index(index);
+                };
                 match key.code {
                     KeyCode::Char('q') => self.ui_state.popup_state = PopupState::None,
-                    KeyCode::Left => {
-                        if buttons_index > 0 {
-                            buttons[buttons_index].set_state(ButtonState::Normal);
-                            buttons[buttons_index].set_state(ButtonState::Selected);
-                            self.ui_state.options.get_buttons_index().sub_assign(1);
-                        }
-                    }
-                    KeyCode::Right => {
-                        if buttons_index < buttons.len() - 1 {
-                            buttons[buttons_index].set_state(ButtonState::Normal);
-                            buttons[buttons_index].set_state(ButtonState::Selected);
-                            self.ui_state.options.get_buttons_index().add_assign(1);
-                        }
+                    KeyCode::Left if buttons_index > 0 => select(self, buttons_index - 1),
+                    KeyCode::Right if buttons_index + 1 < buttons_len => {
+                        select(self, buttons_index + 1)
                     }
                     KeyCode::Enter => {
-                        match buttons_index {
-                            0 => self
-                                .ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::CategoryPopup),
-                            1 => self
-                                .ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::DifficultyPopup),
-                            2 => self
-                                .ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::StatusPopup),
-                            3 => self
-                                .ui_state
-                                .options
-                                .get_mut_popup()
-                                .set_state(OptionsPopupState::OrderPopup),
-                            _ => {}
-                        }
+                        self.ui_state.options.open_popup(buttons_index);
                         self.ui_state.popup_state = PopupState::Options;
                     }
                     _ => {}
@@ -342,6 +344,13 @@ mod keyboard {
                 KeyCode::Char('G') | KeyCode::End => self.ui_state.challenges.select_last(),
                 KeyCode::Char('l') | KeyCode::Right => self.next_page(),
                 KeyCode::Char('u') | KeyCode::Left => self.previous_page(),
+                KeyCode::Char('t') => self.toggle_list_view(),
+                KeyCode::Char('d') => self.handle_download_file(),
+                KeyCode::Char('D') => self.queue_page(),
+                KeyCode::Char('a') => self.queue_all_pages(),
+                KeyCode::Char(c @ '1'..='6') => {
+                    self.sort_by(TableColumn::ALL[c as usize - '1' as usize]);
+                }
                 _ => {}
             }
         }
@@ -349,35 +358,28 @@ mod keyboard {
         fn handle_wargame_details_input(&mut self, key: KeyEvent) {
             #[cfg(debug_assertions)]
             log::info!("Handle wargame details input");
+            // Flags may contain any letter, so the Enter Flag box gets them before
+            // the shortcuts below, and is left with Up/Down
+            let in_flag_box = self.ui_state.wargame_details_index == 0;
             match key.code {
-                KeyCode::Char('q') => self.should_exit = true,
-                KeyCode::Char('k') | KeyCode::Up => {
-                    if self.ui_state.wargame_details_index > 0 {
-                        self.ui_state.wargame_details_index -= 1;
-                    }
+                KeyCode::PageUp => self.scroll_details(-DETAILS_PAGE_SIZE),
+                KeyCode::PageDown => self.scroll_details(DETAILS_PAGE_SIZE),
+                KeyCode::Enter if in_flag_box => self.handle_submit_flag(),
+                KeyCode::Char(to_insert) if in_flag_box => {
+                    self.ui_state.enter_flag.enter_char(to_insert)
                 }
-                KeyCode::Char('j') | KeyCode::Down => {
-                    if self.ui_state.wargame_details_index < 2 {
-                        self.ui_state.wargame_details_index += 1;
-                    }
+                KeyCode::Backspace if in_flag_box => self.ui_state.enter_flag.delete_char(),
+                KeyCode::Left if in_flag_box => self.ui_state.enter_flag.move_cursor_left(),
+                KeyCode::Right if in_flag_box => self.ui_state.enter_flag.move_cursor_right(),
+                KeyCode::Char('q') => self.should_exit = true,
+                KeyCode::Char('k') | KeyCode::Up if self.ui_state.wargame_details_index > 0 => {
+                    self.ui_state.wargame_details_index -= 1;
                 }
-                _ => {}
-            }
-
-            match self.ui_state.wargame_details_index {
-                0 => {
-                    self.ui_state.cursor_state = CursorState::EnterFlag;
-                    match key.code {
-                        KeyCode::Enter => {}
-                        KeyCode::Char(to_insert) => self.ui_state.enter_flag.enter_char(to_insert),
-                        KeyCode::Backspace => self.ui_state.enter_flag.delete_char(),
-                        KeyCode::Left => self.ui_state.enter_flag.move_cursor_left(),
-                        KeyCode::Right => self.ui_state.enter_flag.move_cursor_right(),
-                        _ => {}
-                    }
+                KeyCode::Char('j') | KeyCode::Down if self.ui_state.wargame_details_index < 4 => {
+                    self.ui_state.wargame_details_index += 1;
                 }
-                1 => {
-                    if key.code == KeyCode::Enter {
+                KeyCode::Enter => match self.ui_state.wargame_details_index {
+                    1 => {
                         #[cfg(debug_assertions)]
                         log::info!(
                             "Selected item: {:?}",
@@ -386,12 +388,58 @@ mod keyboard {
 
                         self.handle_download_file();
                     }
+                    2 => self.handle_create_vm(),
+                    3 => self.handle_extend_vm(),
+                    4 => self.handle_stop_vm(),
+                    _ => {}
+                },
+                _ => {}
+            }
+
+            if self.ui_state.wargame_details_index == 0 {
+                self.ui_state.cursor_state = CursorState::EnterFlag;
+            }
+        }
+
+        fn handle_downloads_popup_input(&mut self, key: KeyEvent) {
+            #[cfg(debug_assertions)]
+            log::info!("Handle downloads popup input");
+            let downloads = &mut self.ui_state.downloads;
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.ui_state.popup_state = PopupState::None,
+                KeyCode::Char('j') | KeyCode::Down => downloads.items.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => downloads.items.select_previous(),
+                KeyCode::Char('r') => {
+                    downloads.retry_selected();
+                    self.start_queued_downloads();
                 }
-                2 => {
-                    if key.code == KeyCode::Enter {
-                        self.handle_create_vm();
-                    }
+                KeyCode::Char('R') => {
+                    downloads.retry_failed();
+                    self.start_queued_downloads();
                 }
+                KeyCode::Char('x') | KeyCode::Delete => self.cancel_selected_download(),
+                KeyCode::Char('c') => downloads.clear_finished(),
+                KeyCode::Char('p') => self.preview_selected_download(),
+                _ => {}
+            }
+        }
+
+        fn handle_archive_preview_input(&mut self, key: KeyEvent) {
+            #[cfg(debug_assertions)]
+            log::info!("Handle archive preview input");
+            let Some(preview) = self.ui_state.preview.as_mut() else {
+                return;
+            };
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.close_archive_preview(),
+                KeyCode::Char('j') | KeyCode::Down => preview.entries.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => preview.entries.select_previous(),
+                KeyCode::Char('g') | KeyCode::Home => preview.entries.select_first(),
+                KeyCode::Char('G') | KeyCode::End => preview.entries.select_last(),
+                KeyCode::Char(' ') => preview.toggle_selected(),
+                KeyCode::Char('a') => preview.toggle_all(),
+                KeyCode::Char('f') => preview.toggle_flatten(),
+                KeyCode::Enter => self.extract_preview(),
                 _ => {}
             }
         }
@@ -1,17 +1,24 @@
+mod cli;
 mod config;
+mod credentials;
 mod custom_widgets;
+mod description;
+mod downloads;
 /// Module: dreamhack
 ///
 /// This module is for handling Dreamhack API.
 mod dreamhack;
+mod event_handler;
 mod fs_tree;
 mod render;
-mod event_handler;
+mod tasks;
 mod termui;
 mod utils;
 
-use std::io;
+use std::{io, process::ExitCode};
 
+use clap::Parser;
+use cli::Cli;
 use color_eyre::Result;
 use config::Config;
 use crossterm::{
@@ -19,8 +26,6 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use dialoguer::Input;
-use keyring::Entry;
 #[cfg(debug_assertions)]
 use log::LevelFilter;
 #[cfg(debug_assertions)]
@@ -29,10 +34,12 @@ use log4rs::{
     config::{Appender, Root},
     encode::pattern::PatternEncoder,
 };
-use rpassword::prompt_password;
 use termui::App;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
+    // Parse arguments first, `--help` or a typo must not touch the disk
+    let cli = Cli::parse();
+
     // Logger initialization (For debugging)
     #[cfg(debug_assertions)]
     {
@@ -51,46 +58,27 @@ fn main() -> Result<()> {
 
     let config = Config::read_or_new_config();
 
-    // Create separate entries for email and password
-    let email_entry = Entry::new("DreamhackService", "dreamhack_email").unwrap();
-    let password_entry = Entry::new("DreamhackService", "dreamhack_password").unwrap();
-
-    let email: String;
-    let password: String;
+    if let Some(command) = cli.command {
+        color_eyre::install()?;
+        return cli::run(command, cli.format, config);
+    }
 
     // Check if email and password are already stored
-    let email_result = email_entry.get_secret();
-    let password_result = password_entry.get_password();
-
-    if email_result.is_err() || password_result.is_err() {
-        email = Input::new().with_prompt("Email").interact().unwrap();
-        password = prompt_password("Password: ").unwrap();
-
-        // Save email and password
-        match email_entry.set_secret(email.as_bytes()) {
-            Ok(()) => println!("Successfully set email"),
-            #[allow(unused_variables)]
-            Err(err) => {
-                #[cfg(debug_assertions)]
-                log::error!("Error setting email: {err}")
+    let (email, password) = match credentials::load_login() {
+        Some((email, password)) => {
+            #[cfg(debug_assertions)]
+            {
+                log::info!("Retrieved email: {}", email);
+                log::info!("Retrieved password: (hidden)");
             }
+            (email, password)
         }
-        match password_entry.set_password(&password) {
-            Ok(()) => println!("Successfully set password"),
-            #[allow(unused_variables)]
-            Err(err) => {
-                #[cfg(debug_assertions)]
-                log::error!("Error setting password: {err}")
-            }
+        None => {
+            let (email, password) = credentials::prompt_login();
+            credentials::save_login(&email, &password);
+            (email, password)
         }
-    } else {
-        email = String::from_utf8_lossy(&email_result.unwrap()).into_owned();
-        #[cfg(debug_assertions)]
-        {
-            log::info!("Retrieved email: {}", email);
-            log::info!("Retrieved password: (hidden)");
-        }
-    }
+    };
 
     println!("Logged in with email: {}", email);
     println!("Password is securely stored and retrieved.");
@@ -102,7 +90,7 @@ fn main() -> Result<()> {
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal, config, email_entry, password_entry);
+    let app_result = App::default().run(&mut terminal, config, &email, &password);
     // restore terminal
     disable_raw_mode()?;
     execute!(
@@ -111,5 +99,5 @@ fn main() -> Result<()> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
-    app_result
+    app_result.map(|()| ExitCode::SUCCESS)
 }
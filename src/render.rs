@@ -1,46 +1,269 @@
+use chrono::{Local, Utc};
 use ratatui::{
     layout::*,
     style::*,
     symbols,
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::*,
     Frame,
 };
 use tui_tree_widget::Tree;
 
 use crate::{
+    config::{ListColumn, ListView, Theme},
     custom_widgets::popup::*,
-    dreamhack::{challenge::handle::ToDetailedInfo, options::*, ToColorString},
+    description::render_description,
+    downloads::QueueStatus,
+    dreamhack::{
+        challenge::handle::{format_age, Challenge, ToDetailedInfo, ToSimpleInfo},
+        options::*,
+        vm_info::Protocol,
+        ToColorString,
+    },
+    tasks::TaskKind,
     termui::*,
+    utils::downloader::{format_size, Progress},
 };
 
 pub const WARGAME_BLOCK_SIZE: usize = 4;
 
+/// Cells of the download progress bar in the Details title
+const DOWNLOAD_BAR_WIDTH: usize = 20;
+/// Rows of the extraction report in the Downloads popup, borders included
+const DOWNLOAD_REPORT_HEIGHT: u16 = 10;
+
 pub(crate) const fn alternate_colors(i: usize) -> Color {
-    if i % 2 == 0 {
+    if i.is_multiple_of(2) {
         NORMAL_ROW_BG
     } else {
         ALT_ROW_BG_COLOR
     }
 }
 
+/// Glyph badges derived from the challenge flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Badge {
+    Solved,
+    Attempted,
+    NeedsVm,
+    Official,
+    Featured,
+    AuthorWriteup,
+}
+
+impl Badge {
+    pub(crate) fn of(challenge: &Challenge) -> Vec<Badge> {
+        let flags = challenge.get_metadata().get_flags();
+
+        [
+            (flags.is_completed(), Badge::Solved),
+            (
+                flags.is_attempted() && !flags.is_completed(),
+                Badge::Attempted,
+            ),
+            (flags.needs_vm(), Badge::NeedsVm),
+            (flags.is_official(), Badge::Official),
+            (flags.is_featured(), Badge::Featured),
+            (flags.has_author_writeup(), Badge::AuthorWriteup),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, badge)| enabled.then_some(badge))
+        .collect()
+    }
+
+    fn glyph(&self) -> &'static str {
+        match self {
+            Badge::Solved => "✔",
+            Badge::Attempted => "◐",
+            Badge::NeedsVm => "⚙",
+            Badge::Official => "★",
+            Badge::Featured => "♦",
+            Badge::AuthorWriteup => "✎",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Badge::Solved => Color::LightGreen,
+            Badge::Attempted => Color::LightYellow,
+            Badge::NeedsVm => Color::LightCyan,
+            Badge::Official => Color::Yellow,
+            Badge::Featured => Color::LightMagenta,
+            Badge::AuthorWriteup => Color::LightBlue,
+        }
+    }
+
+    /// " ✔" after a title, or "✔ Solved  " with `labels` (Details pane)
+    pub(crate) fn to_span(self, theme: Theme, labels: bool) -> Span<'static> {
+        match labels {
+            true => Span::styled(
+                format!("{} {}  ", self.glyph(), self),
+                theme.fg(self.color()),
+            ),
+            false => Span::styled(format!(" {}", self.glyph()), theme.fg(self.color())),
+        }
+    }
+}
+
+impl std::fmt::Display for Badge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Badge::Solved => write!(f, "Solved"),
+            Badge::Attempted => write!(f, "Attempted"),
+            Badge::NeedsVm => write!(f, "Needs VM"),
+            Badge::Official => write!(f, "Official"),
+            Badge::Featured => write!(f, "Featured"),
+            Badge::AuthorWriteup => write!(f, "Author writeup"),
+        }
+    }
+}
+
+/// `━━━━━━──────` filled up to the progress, all dashes while the size is unknown.
+fn progress_bar(progress: &Progress, width: usize) -> String {
+    let filled = progress
+        .get_ratio()
+        .map_or(0, |ratio| (ratio * width as f64).round() as usize);
+    format!("{}{}", "━".repeat(filled), "─".repeat(width - filled))
+}
+
+/// Solved challenges are tinted (or dimmed without colours), so unsolved ones stand out.
+pub(crate) fn challenge_row_style(challenge: &Challenge, theme: Theme) -> Style {
+    let flags = challenge.get_metadata().get_flags();
+
+    match (flags.is_completed(), theme) {
+        (true, Theme::Default) => Style::default().fg(Color::Green),
+        (true, Theme::NoColor) => Style::default().add_modifier(Modifier::DIM),
+        (false, _) => Style::default(),
+    }
+}
+
+fn list_column_span(column: ListColumn, challenge: &Challenge, theme: Theme) -> Span<'static> {
+    let metadata = challenge.get_metadata();
+    let stats = metadata.get_stats();
+
+    match column {
+        ListColumn::Solvers => Span::styled(
+            format!("Solvers: {}", stats.solvers),
+            theme.fg(Color::LightGreen),
+        ),
+        ListColumn::Votes => Span::styled(
+            format!("Votes: {}", stats.votes),
+            theme.fg(Color::LightYellow),
+        ),
+        ListColumn::Age => Span::styled(
+            format!(
+                "Age: {}",
+                metadata.get_age().map_or("-".to_string(), format_age)
+            ),
+            theme.fg(Color::Gray),
+        ),
+        ListColumn::Writeup => match metadata.get_flags().has_author_writeup() {
+            true => Span::styled("Writeup: ✓", theme.fg(Color::LightCyan)),
+            false => Span::styled(
+                format!("Writeups: {}", stats.writeups),
+                theme.fg(Color::Gray),
+            ),
+        },
+    }
+}
+
 impl App {
-    pub(crate) fn render_header(area: Rect, frame: &mut Frame) {
-        Paragraph::new("CreamHack")
+    pub(crate) fn render_header(&self, area: Rect, frame: &mut Frame) {
+        let running = [
+            TaskKind::Login,
+            TaskKind::SyncCatalog,
+            TaskKind::CollectPages,
+        ]
+        .into_iter()
+        .find(|kind| self.tasks.is_running(*kind));
+        let mut header = vec![Span::raw(match running {
+            Some(kind) => format!("CreamHack {} {}...", self.tasks.spinner(), kind),
+            None => "CreamHack".to_string(),
+        })];
+
+        if self.ui_state.offline {
+            header.push(Span::styled(
+                " [Offline]",
+                self.ui_state.theme.fg(Color::LightYellow),
+            ));
+        }
+        if self.ui_state.live_search {
+            header.push(Span::styled(
+                " [Live]",
+                self.ui_state.theme.fg(Color::LightCyan),
+            ));
+        }
+        if let Some(catalog) = self.get_local_catalog() {
+            let synced = catalog
+                .get_synced_at()
+                .map_or("never synced".to_string(), |time| {
+                    format!("synced {} ago", format_age(Utc::now() - time))
+                });
+            header.push(Span::styled(
+                format!(" [Local, {}]", synced),
+                self.ui_state.theme.fg(Color::LightGreen),
+            ));
+        }
+        let downloads = &self.ui_state.downloads;
+        let (running, queued, failed) = (
+            downloads.count(|status| *status == QueueStatus::Running),
+            downloads.count(|status| *status == QueueStatus::Queued),
+            downloads.count(|status| matches!(status, QueueStatus::Failed(_))),
+        );
+        if running + queued + failed > 0 {
+            let mut summary = vec![format!("{} downloading", running)];
+            if queued > 0 {
+                summary.push(format!("{} queued", queued));
+            }
+            if failed > 0 {
+                summary.push(format!("{} failed", failed));
+            }
+            header.push(Span::styled(
+                format!(" [{}]", summary.join(", ")),
+                self.ui_state.theme.fg(match failed {
+                    0 => Color::LightBlue,
+                    _ => Color::LightRed,
+                }),
+            ));
+        }
+        if let Some(stale_since) = self.ui_state.stale_since {
+            header.push(Span::styled(
+                format!(
+                    " stale since {} ({} ago)",
+                    stale_since.with_timezone(&Local).format("%m-%d %H:%M"),
+                    format_age(Utc::now() - stale_since)
+                ),
+                self.ui_state.theme.fg(Color::DarkGray),
+            ));
+        }
+
+        Paragraph::new(Line::from(header))
             .bold()
             .centered()
             .render(area, frame.buffer_mut());
     }
 
-    pub(crate) fn render_footer(area: Rect, frame: &mut Frame) {
-        Paragraph::new("Author: KimWang906")
-            .style(Style::default().bold())
-            .centered()
-            .render(area, frame.buffer_mut());
+    pub(crate) fn render_footer(&self, area: Rect, frame: &mut Frame) {
+        match &self.ui_state.error {
+            Some(error) => Paragraph::new(format!("Error: {}", error))
+                .style(Style::default().fg(Color::LightRed).bold())
+                .centered()
+                .render(area, frame.buffer_mut()),
+            None if self.ui_state.notice.is_some() => {
+                Paragraph::new(self.ui_state.notice.clone().unwrap_or_default())
+                    .style(self.ui_state.theme.fg(Color::LightGreen).bold())
+                    .centered()
+                    .render(area, frame.buffer_mut())
+            }
+            None => Paragraph::new("Author: KimWang906")
+                .style(Style::default().bold())
+                .centered()
+                .render(area, frame.buffer_mut()),
+        }
     }
 
     pub(crate) fn render_search(&mut self, area: Rect, frame: &mut Frame) {
-        let block = Block::default()
+        let mut block = Block::default()
             .title(Line::raw("Search").centered())
             .borders(Borders::ALL)
             .border_set(symbols::border::ROUNDED);
@@ -49,7 +272,27 @@ impl App {
             self.handle_search_mouse_event(mouse, area);
         }
 
-        Paragraph::new(self.ui_state.search.input.as_str())
+        // Parse errors underline the term and are shown on the bottom border
+        let input = self.ui_state.search.input.as_str();
+        let line = match &self.ui_state.query_error {
+            Some(error) => {
+                let error_style = Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::UNDERLINED);
+                block = block.title_bottom(Line::styled(
+                    format!(" {} ", error),
+                    Style::default().fg(Color::LightRed),
+                ));
+                Line::from(vec![
+                    Span::raw(&input[..error.span.start]),
+                    Span::styled(&input[error.span.clone()], error_style),
+                    Span::raw(&input[error.span.end..]),
+                ])
+            }
+            None => Line::raw(input),
+        };
+
+        Paragraph::new(line)
             .block(block)
             .fg(TEXT_FG_COLOR)
             .render(area, frame.buffer_mut());
@@ -67,13 +310,12 @@ impl App {
         let selected_index = self.ui_state.options.get_selected_index();
 
         // Create the layout for the options
-        let options: [Rect; 4] = Layout::horizontal([
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-        ])
-        .areas(area);
+        let options = Layout::horizontal(
+            buttons
+                .iter()
+                .map(|_| Constraint::Ratio(1, buttons.len() as u32)),
+        )
+        .split(area);
 
         for (i, button) in buttons.iter().enumerate() {
             let block = Block::default()
@@ -100,8 +342,13 @@ impl App {
         let popup_rect = popup_area(frame.area(), 50, 50);
         frame.render_widget(Clear, popup_rect);
 
+        let hint = match self.ui_state.options.get_popup().get_marks() {
+            Some(_) => " Space: mark  Enter: apply  Esc: cancel ",
+            None => " Enter: apply  Esc: cancel ",
+        };
         let block = Block::default()
             .title("Edit Option")
+            .title_bottom(Line::from(hint).centered())
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
             .style(Style::default().bg(Color::DarkGray));
@@ -120,25 +367,50 @@ impl App {
             OptionsPopupState::OrderPopup => {
                 self.popup_order(popup_rect, frame);
             }
+            OptionsPopupState::TypePopup => {
+                self.popup_type(popup_rect, frame);
+            }
+            OptionsPopupState::ScopePopup => {
+                self.popup_scope(popup_rect, frame);
+            }
             _ => {}
         }
     }
 
     pub(crate) fn render_options_value(&mut self, area: Rect, frame: &mut Frame) {
-        let values_area: [Rect; 4] = Layout::horizontal([
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-        ])
-        .areas(area);
+        let buttons_len = self.ui_state.options.get_buttons().len();
+        let values_area =
+            Layout::horizontal((0..buttons_len).map(|_| Constraint::Ratio(1, buttons_len as u32)))
+                .split(area);
 
         let items = self.ui_state.options.get_items();
+        let categories = match items.get_categories() {
+            [] => Category::All.to_string(),
+            [category] => category.to_string(),
+            [first, second] => format!("{}, {}", first, second),
+            [first, rest @ ..] => format!("{} +{}", first, rest.len()),
+        };
+        let (min, max) = match items.get_levels() {
+            _ if items.is_beginner() => (Difficulty::Beginner, Difficulty::Beginner),
+            Some(range) => (
+                Difficulty::from(range.get_min()),
+                Difficulty::from(range.get_max()),
+            ),
+            None => (Difficulty::All, Difficulty::All),
+        };
+        let levels = match min == max {
+            true => Line::from(min.to_color_string()),
+            false => Line::from(vec![
+                min.to_color_string(),
+                Span::raw(" ~ "),
+                max.to_color_string(),
+            ]),
+        };
         let values = [
-            Paragraph::new(items.get_category().to_string())
+            Paragraph::new(categories)
                 .style(Style::new().bold())
                 .alignment(Alignment::Center),
-            Paragraph::new(items.get_difficulty().to_color_string())
+            Paragraph::new(levels)
                 .style(Style::new().bold())
                 .alignment(Alignment::Center),
             Paragraph::new(items.get_status().to_string())
@@ -147,6 +419,12 @@ impl App {
             Paragraph::new(items.get_order().to_string())
                 .style(Style::new().bold())
                 .alignment(Alignment::Center),
+            Paragraph::new(items.get_type().to_string())
+                .style(Style::new().bold())
+                .alignment(Alignment::Center),
+            Paragraph::new(items.get_scope().to_string())
+                .style(Style::new().bold())
+                .alignment(Alignment::Center),
         ];
 
         for (i, value) in values.iter().enumerate() {
@@ -171,8 +449,13 @@ impl App {
 
     // 리스트 렌더링 함수
     pub(crate) fn render_list(&mut self, area: Rect, frame: &mut Frame) {
+        let title = match self.tasks.is_running(TaskKind::Search) {
+            true => format!("Wargames {} {}...", self.tasks.spinner(), TaskKind::Search),
+            false => "Wargames".to_string(),
+        };
+
         let block = Block::new()
-            .title(Line::raw("Wargames").centered())
+            .title(Line::raw(title).centered())
             .borders(Borders::TOP)
             .border_set(symbols::border::EMPTY)
             .border_style(CREAMHACK_HEADER_STYLE)
@@ -184,6 +467,17 @@ impl App {
             }
         }
 
+        if self.ui_state.list_view == ListView::Table {
+            self.render_table(area, block, frame);
+            return;
+        }
+
+        let columns = self
+            .config
+            .as_ref()
+            .map_or(Vec::new(), |config| config.list_columns.clone());
+
+        let theme = self.ui_state.theme;
         let items: Vec<ListItem> = self
             .ui_state
             .challenges
@@ -192,7 +486,24 @@ impl App {
             .enumerate()
             .map(|(i, chall_item)| {
                 let color = alternate_colors(i);
-                ListItem::from(chall_item).bg(color)
+                let mut text = chall_item.to_simple_info();
+
+                if let Some(line) = text.lines.get_mut(0) {
+                    for badge in Badge::of(chall_item) {
+                        line.push_span(badge.to_span(theme, false));
+                    }
+                }
+
+                // Optional columns go after the author, so the item height stays WARGAME_BLOCK_SIZE
+                if let Some(line) = text.lines.get_mut(2) {
+                    for column in columns.iter() {
+                        line.push_span(Span::raw("  "));
+                        line.push_span(list_column_span(*column, chall_item, theme));
+                    }
+                }
+                ListItem::new(text)
+                    .style(challenge_row_style(chall_item, theme))
+                    .bg(color)
             })
             .collect();
 
@@ -209,45 +520,242 @@ impl App {
         }
     }
 
+    /// Compact view of `render_list`, one row per challenge.
+    fn render_table(&mut self, area: Rect, block: Block, frame: &mut Frame) {
+        let sort = self.ui_state.table_sort;
+        let theme = self.ui_state.theme;
+        let header = TableColumn::ALL
+            .iter()
+            .map(|column| match sort {
+                Some(sort) if sort.column == *column => {
+                    format!("{}{}", column, if sort.descending { "▼" } else { "▲" })
+                }
+                _ => column.to_string(),
+            })
+            .collect::<Row>()
+            .style(CREAMHACK_HEADER_STYLE.add_modifier(Modifier::BOLD));
+
+        let rows = self
+            .ui_state
+            .challenges
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, challenge)| {
+                let info = challenge.get_info();
+                let metadata = challenge.get_metadata();
+                let flags = metadata.get_flags();
+
+                let status = match (flags.is_completed(), flags.is_attempted()) {
+                    (true, _) => Span::styled("✔ Solved", theme.fg(Color::LightGreen)),
+                    (false, true) => Span::styled("◐ Tried", theme.fg(Color::LightYellow)),
+                    (false, false) => Span::raw("-"),
+                };
+
+                let difficulty = info.get_difficulty();
+                let level = difficulty.to_color_string();
+
+                // Solved/needs-VM have their own columns, the title keeps the other badges
+                let title = std::iter::once(Span::raw(info.get_title()))
+                    .chain(
+                        Badge::of(challenge)
+                            .into_iter()
+                            .filter(|badge| {
+                                !matches!(badge, Badge::Solved | Badge::Attempted | Badge::NeedsVm)
+                            })
+                            .map(|badge| badge.to_span(theme, false)),
+                    )
+                    .collect::<Line>();
+
+                Row::new([
+                    Cell::from(title),
+                    Cell::from(Span::styled(level.content.into_owned(), level.style)),
+                    Cell::from(info.get_tags().join(", ")),
+                    Cell::from(metadata.get_stats().solvers.to_string()),
+                    Cell::from(status),
+                    Cell::from(Span::styled(
+                        if flags.needs_vm() { "⚙" } else { "" },
+                        theme.fg(Color::LightCyan),
+                    )),
+                ])
+                .style(challenge_row_style(challenge, theme))
+                .bg(alternate_colors(i))
+            })
+            .collect::<Vec<Row>>();
+
+        let table = Table::new(rows, TableColumn::ALL.map(|column| column.width()))
+            .header(header)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+
+        // The list state stays the single source of the selection in both views
+        let list_state = &mut self.ui_state.challenges.state;
+        let mut table_state = TableState::new()
+            .with_offset(list_state.offset())
+            .with_selected(list_state.selected());
+
+        frame.render_stateful_widget(table, area, &mut table_state);
+        *list_state.offset_mut() = table_state.offset();
+        list_state.select(table_state.selected());
+
+        if let Some(mouse) = self.events.mouse {
+            self.handle_wargames_mouse_event(mouse, area);
+        }
+    }
+
     pub(crate) fn render_selected_item(&mut self, area: Rect, frame: &mut Frame) {
-        let [detail_area, enter_flag_area, buttons_area, vm_info_area, unused_area] =
+        // Per port mapping: protocol, internal port and each way to connect, one per line
+        let mappings = self
+            .vm_state
+            .vm_info
+            .get_network_info()
+            .iter()
+            .flat_map(|info| {
+                let mut uris = vec![info.get_nc_command().unwrap_or_else(|| info.get_uri_pwn())];
+                if matches!(info.protocol, Protocol::Tcp) {
+                    uris.push(info.get_uri_web());
+                }
+                let label = format!("{} :{} → ", info.protocol, info.internal);
+                uris.into_iter().map(move |uri| {
+                    Line::from(vec![
+                        Span::styled(label.clone(), Style::default().fg(Color::LightCyan)),
+                        Span::raw(uri),
+                    ])
+                })
+            })
+            .collect::<Vec<Line>>();
+
+        let [detail_area, enter_flag_area, buttons_area, vm_info_area, vm_status_area] =
             Layout::vertical([
                 Constraint::Percentage(50),
                 Constraint::Length(3),
                 Constraint::Length(6),
-                Constraint::Length(2),
+                Constraint::Length(mappings.len().max(1) as u16),
                 Constraint::Fill(1),
             ])
             .areas(area);
 
-        let buttons_area =
-            Layout::vertical([Constraint::Length(3), Constraint::Length(3)]).split(buttons_area);
+        let [download_area, vm_buttons_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Length(3)]).areas(buttons_area);
+        let [create_vm_area, extend_vm_area, stop_vm_area] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+        ])
+        .areas(vm_buttons_area);
+        let buttons_area = [download_area, create_vm_area, extend_vm_area, stop_vm_area];
 
         let info = if let Some(i) = self.ui_state.challenges.state.selected() {
             if i < self.ui_state.challenges.items.len() {
-                format!("{}", self.ui_state.challenges.items[i].to_detailed_info())
+                let challenge = &self.ui_state.challenges.items[i];
+                let mut info = Text::raw(format!("{}", challenge.to_detailed_info()));
+
+                // Descriptions are rendered once per selected challenge
+                let details = &mut self.ui_state.details;
+                if details.challenge_id != Some(challenge.get_id()) {
+                    details.challenge_id = Some(challenge.get_id());
+                    details.description = render_description(
+                        challenge.get_info().get_description(),
+                        self.ui_state.theme,
+                    );
+                    details.scroll = 0;
+                }
+
+                let badges = Badge::of(challenge)
+                    .into_iter()
+                    .map(|badge| badge.to_span(self.ui_state.theme, true))
+                    .collect::<Line>();
+                if !badges.spans.is_empty() {
+                    info.lines.insert(1.min(info.lines.len()), badges);
+                }
+
+                // Show the verdict of the last flag submission for this challenge
+                if let Some((id, result)) = &self.ui_state.flag_result {
+                    if *id == challenge.get_id() {
+                        info.push_line(Line::from(vec!["Flag: ".into(), result.to_color_string()]));
+                    }
+                }
+
+                info.push_line(Line::styled(
+                    "Description",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+                info.extend(self.ui_state.details.description.lines.iter().cloned());
+                info
             } else {
                 self.ui_state.challenges.select_last();
-                "Nothing selected...".to_string()
+                Text::raw("Nothing selected...")
             }
         } else {
-            "Nothing selected...".to_string()
+            Text::raw("Nothing selected...")
+        };
+
+        // The download of the selected challenge, if it is running
+        let download = self
+            .ui_state
+            .challenges
+            .state
+            .selected()
+            .and_then(|i| self.ui_state.challenges.items.get(i))
+            .and_then(|challenge| self.ui_state.downloads.get_running(challenge.get_id()));
+        let title = match download {
+            Some(item) => format!(
+                "Details {} Downloading {} {}",
+                self.tasks.spinner(),
+                progress_bar(item.get_progress(), DOWNLOAD_BAR_WIDTH),
+                item.get_progress()
+            ),
+            None => [
+                TaskKind::CreateVm,
+                TaskKind::ExtendVm,
+                TaskKind::StopVm,
+                TaskKind::SubmitFlag,
+            ]
+            .into_iter()
+            .find(|kind| self.tasks.is_running(*kind))
+            .map_or("Details".to_string(), |kind| {
+                format!("Details {} {}...", self.tasks.spinner(), kind)
+            }),
         };
 
         let details_block = Block::new()
-            .title(Line::raw("Details").centered())
+            .title(Line::raw(title).centered())
             .borders(Borders::TOP)
             .border_set(symbols::border::EMPTY)
             .border_style(CREAMHACK_HEADER_STYLE)
             .bg(NORMAL_ROW_BG)
             .padding(Padding::horizontal(1));
 
+        // Wrapped lines are not counted, so this only keeps the scroll roughly in range
+        let line_count = info.lines.len();
+        let details = &mut self.ui_state.details;
+        details.scroll = details.scroll.min(line_count.saturating_sub(1) as u16);
+
         Paragraph::new(info)
             .block(details_block)
             .fg(TEXT_FG_COLOR)
             .wrap(Wrap { trim: false })
+            .scroll((details.scroll, 0))
             .render(detail_area, frame.buffer_mut());
 
+        let mut scrollbar_state = ScrollbarState::new(line_count).position(details.scroll as usize);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            detail_area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+
+        if let Some(mouse) = self.events.mouse {
+            self.handle_details_mouse_event(mouse, detail_area);
+        }
+
         let enter_flag_block = Block::default()
             .title(Line::raw("Enter Flag").centered())
             .borders(Borders::ALL)
@@ -271,7 +779,7 @@ impl App {
             ));
         }
 
-        let buttons = ["Download Challenges", "Create VM"];
+        let buttons = ["Download Challenges", "Create VM", "Extend VM", "Stop VM"];
         for (i, &button) in buttons.iter().enumerate() {
             let block = Block::default()
                 .borders(Borders::ALL)
@@ -294,31 +802,53 @@ impl App {
             frame.render_widget(paragraph, buttons_area[i]);
         }
 
-        match self.vm_state.vm_info.get_network_info() {
-            Some(network_info) => {
-                Paragraph::new(Text::from(vec![
-                    Line::raw(format!(
-                        "System Hacking: nc {}\n",
-                        network_info.get_uri_pwn()
-                    )),
-                    Line::raw(format!("Web Hacking: {}\n", network_info.get_uri_web())),
-                ]))
-                .centered()
-                .bg(NORMAL_ROW_BG)
-                .render(vm_info_area, frame.buffer_mut());
-            }
-            None => {
-                Paragraph::new("")
-                    .centered()
-                    .bg(NORMAL_ROW_BG)
-                    .render(vm_info_area, frame.buffer_mut());
+        Paragraph::new(mappings)
+            .centered()
+            .bg(NORMAL_ROW_BG)
+            .render(vm_info_area, frame.buffer_mut());
+
+        let vm_info = &self.vm_state.vm_info;
+        let vm_status = match (&self.vm_state.challenge, vm_info.get_remaining()) {
+            (Some(challenge), Some(remaining)) => {
+                let remaining = remaining.num_seconds();
+                let mut status = Text::raw(format!(
+                    "{} ({})",
+                    challenge.get_info().get_title(),
+                    vm_info.get_state()
+                ));
+                if let Some(starttime) = vm_info.get_starttime() {
+                    status.push_line(format!(
+                        "Started: {}",
+                        starttime.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+                    ));
+                }
+                status.push_line(Line::styled(
+                    format!(
+                        "Remaining: {:02}:{:02}:{:02}",
+                        remaining / 3600,
+                        remaining / 60 % 60,
+                        remaining % 60
+                    ),
+                    if remaining < 600 {
+                        Color::LightRed
+                    } else {
+                        TEXT_FG_COLOR
+                    },
+                ));
+                status
             }
-        }
+            (Some(challenge), None) => Text::raw(format!(
+                "{} ({})",
+                challenge.get_info().get_title(),
+                vm_info.get_state()
+            )),
+            (None, _) => Text::raw("No VM running"),
+        };
 
-        Paragraph::new("unimplemented...")
+        Paragraph::new(vm_status)
             .centered()
             .bg(NORMAL_ROW_BG)
-            .render(unused_area, frame.buffer_mut())
+            .render(vm_status_area, frame.buffer_mut())
     }
 
     pub(crate) fn render_fs_tree_view_popup(&mut self, frame: &mut Frame) {
@@ -344,17 +874,226 @@ impl App {
 
         frame.render_stateful_widget(widget, popup_rect, &mut self.fs_state.tree_state);
     }
+
+    pub(crate) fn render_downloads_popup(&mut self, frame: &mut Frame) {
+        let popup_rect = popup_area(frame.area(), 70, 60);
+        frame.render_widget(Clear, popup_rect);
+
+        let theme = self.ui_state.theme;
+        let items = self
+            .ui_state
+            .downloads
+            .items
+            .items
+            .iter()
+            .map(|item| {
+                let progress = item.get_progress();
+                let (glyph, color, detail) = match item.get_status() {
+                    QueueStatus::Queued => ("·", Color::DarkGray, "queued".to_string()),
+                    QueueStatus::Running => (
+                        self.tasks.spinner(),
+                        Color::LightBlue,
+                        format!(
+                            "{} {}",
+                            progress_bar(progress, DOWNLOAD_BAR_WIDTH),
+                            progress
+                        ),
+                    ),
+                    QueueStatus::Saved(path, None) => (
+                        "✔",
+                        Color::LightGreen,
+                        format!("{} (not extracted, p: Preview)", path.display()),
+                    ),
+                    QueueStatus::Saved(path, Some(report)) if report.is_empty() => {
+                        ("✔", Color::LightGreen, path.display().to_string())
+                    }
+                    QueueStatus::Saved(path, Some(report)) => (
+                        "!",
+                        Color::LightYellow,
+                        format!("{} ({})", path.display(), report),
+                    ),
+                    QueueStatus::Skipped(path) => (
+                        "=",
+                        Color::LightYellow,
+                        format!("already at {}", path.display()),
+                    ),
+                    QueueStatus::Failed(error) => ("✘", Color::LightRed, error.clone()),
+                    QueueStatus::Cancelled => ("-", Color::DarkGray, "cancelled".to_string()),
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", glyph), theme.fg(color)),
+                    Span::raw(format!(
+                        "{:<40} ",
+                        item.get_challenge().get_info().get_title()
+                    )),
+                    Span::styled(detail, theme.fg(color)),
+                ]))
+            })
+            .collect::<Vec<ListItem>>();
+
+        let block = Block::bordered()
+            .title(Line::raw("Downloads").centered())
+            .title_bottom(
+            Line::raw(
+                " r: Retry  R: Retry failed  x: Cancel  c: Clear finished  p: Preview  Esc: Close ",
+            )
+            .centered(),
+        );
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">");
+
+        // What extracting the selected download left out, below the list
+        let report =
+            self.ui_state
+                .downloads
+                .get_selected()
+                .and_then(|item| match item.get_status() {
+                    QueueStatus::Saved(_, Some(report)) if !report.is_empty() => {
+                        Some(report.lines())
+                    }
+                    _ => None,
+                });
+        let Some(report) = report else {
+            frame.render_stateful_widget(
+                list,
+                popup_rect,
+                &mut self.ui_state.downloads.items.state,
+            );
+            return;
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),
+                Constraint::Length((report.len() as u16 + 2).min(DOWNLOAD_REPORT_HEIGHT)),
+            ])
+            .split(popup_rect);
+        frame.render_stateful_widget(list, chunks[0], &mut self.ui_state.downloads.items.state);
+
+        let report = Paragraph::new(report.into_iter().map(Line::raw).collect::<Vec<Line>>())
+            .style(theme.fg(Color::LightYellow))
+            .block(Block::bordered().title(Line::raw("Extraction report").centered()));
+        frame.render_widget(report, chunks[1]);
+    }
+
+    pub(crate) fn render_archive_preview_popup(&mut self, frame: &mut Frame) {
+        let Some(preview) = self.ui_state.preview.as_mut() else {
+            return;
+        };
+        let popup_rect = popup_area(frame.area(), 70, 70);
+        frame.render_widget(Clear, popup_rect);
+
+        let theme = self.ui_state.theme;
+        let rows = preview
+            .entries
+            .items
+            .iter()
+            .map(|item| {
+                let entry = item.get_entry();
+                let (mark, style) = match item.is_selected() {
+                    true => ("[x]", Style::default()),
+                    false => ("[ ]", theme.fg(Color::DarkGray)),
+                };
+                Row::new([
+                    mark.to_string(),
+                    entry.name.clone(),
+                    format_size(entry.size),
+                    format_size(entry.compressed_size),
+                    format_mode(entry.mode, entry.is_dir),
+                ])
+                .style(style)
+            })
+            .collect::<Vec<Row>>();
+
+        let header = ["", "Name", "Size", "Packed", "Mode"]
+            .into_iter()
+            .collect::<Row>()
+            .style(CREAMHACK_HEADER_STYLE.add_modifier(Modifier::BOLD));
+
+        let archive = preview
+            .get_archive()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut title = format!(
+            " {} ({} of {} selected",
+            archive,
+            preview.count_selected(),
+            preview
+                .entries
+                .items
+                .iter()
+                .filter(|item| !item.get_entry().is_dir)
+                .count()
+        );
+        let mut hint = " Space: Toggle  a: All".to_string();
+        if let Some(top) = preview.get_top_directory() {
+            if preview.is_flattened() {
+                title.push_str(&format!(", {}/ flattened", top));
+            }
+            hint.push_str(&format!("  f: Flatten {}/", top));
+        }
+        title.push_str(") ");
+        hint.push_str("  Enter: Extract  Esc: Back ");
+
+        let block = Block::bordered()
+            .title(Line::raw(title).centered())
+            .title_bottom(Line::raw(hint).centered());
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(HighlightSpacing::Always);
+
+        // Same bridge from the list state as `render_table`
+        let list_state = &mut preview.entries.state;
+        let mut table_state = TableState::new()
+            .with_offset(list_state.offset())
+            .with_selected(list_state.selected());
+        frame.render_stateful_widget(table, popup_rect, &mut table_state);
+        *list_state.offset_mut() = table_state.offset();
+    }
+}
+
+/// `drwxr-xr-x` style, `-` when the archive has no unix modes.
+fn format_mode(mode: Option<u32>, is_dir: bool) -> String {
+    let Some(mode) = mode else {
+        return "-".to_string();
+    };
+
+    let mut text = String::from(if is_dir { "d" } else { "-" });
+    for shift in [6, 3, 0] {
+        let bits = mode >> shift;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    text
 }
 
 impl PopupOptions for App {
-    fn popup_options<T>(&self, area: Rect, frame: &mut Frame)
+    fn popup_options<T>(&self, area: Rect, frame: &mut Frame, items: &[T], marks: Option<&[bool]>)
     where
-        T: PopupItem + PartialEq,
+        T: ToString,
     {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
-                T::variants()
+                items
                     .iter()
                     .map(|_| Constraint::Length(3))
                     .collect::<Vec<Constraint>>(),
@@ -364,17 +1103,17 @@ impl PopupOptions for App {
                 horizontal: 1,
             }));
 
-        for (i, item) in T::variants().iter().enumerate() {
-            let style = if T::from_index(
-                self.ui_state
-                    .options
-                    .get_popup()
-                    .get_items()
-                    .get(self.ui_state.options.get_buttons_index())
-                    .expect("Failed to get the selected index")
-                    .get_index(),
-            ) == *item
-            {
+        let cursor = self
+            .ui_state
+            .options
+            .get_popup()
+            .get_items()
+            .get(self.ui_state.options.get_buttons_index())
+            .expect("Failed to get the selected index")
+            .get_index();
+
+        for (i, item) in items.iter().enumerate() {
+            let style = if i == cursor {
                 Style::default()
                     .bg(Color::Yellow)
                     .add_modifier(Modifier::BOLD)
@@ -382,7 +1121,11 @@ impl PopupOptions for App {
                 Style::default()
             };
 
-            let item_str = item.to_string();
+            let item_str = match marks.and_then(|marks| marks.get(i)) {
+                Some(true) => format!("[x] {}", item.to_string()),
+                Some(false) => format!("[ ] {}", item.to_string()),
+                None => item.to_string(),
+            };
             let paragraph = Paragraph::new(item_str)
                 .style(style)
                 .alignment(Alignment::Center);
@@ -393,18 +1136,29 @@ impl PopupOptions for App {
 
 impl App {
     pub(crate) fn popup_category(&self, area: Rect, frame: &mut Frame) {
-        self.popup_options::<Category>(area, frame);
+        let marks = self.ui_state.options.get_popup().get_marks();
+        let categories = self.ui_state.options.get_popup().get_categories();
+        self.popup_options(area, frame, categories, marks.as_deref());
     }
 
     pub(crate) fn popup_difficulty(&self, area: Rect, frame: &mut Frame) {
-        self.popup_options::<Difficulty>(area, frame);
+        let marks = self.ui_state.options.get_popup().get_marks();
+        self.popup_options(area, frame, &Difficulty::variants(), marks.as_deref());
     }
 
     pub(crate) fn popup_status(&self, area: Rect, frame: &mut Frame) {
-        self.popup_options::<Status>(area, frame);
+        self.popup_options(area, frame, &Status::variants(), None);
     }
 
     pub(crate) fn popup_order(&self, area: Rect, frame: &mut Frame) {
-        self.popup_options::<Orderings>(area, frame);
+        self.popup_options(area, frame, &Orderings::variants(), None);
+    }
+
+    pub(crate) fn popup_type(&self, area: Rect, frame: &mut Frame) {
+        self.popup_options(area, frame, &ChallengeType::variants(), None);
+    }
+
+    pub(crate) fn popup_scope(&self, area: Rect, frame: &mut Frame) {
+        self.popup_options(area, frame, &Scope::variants(), None);
     }
 }
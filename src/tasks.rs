@@ -0,0 +1,207 @@
+use std::{collections::HashMap, fmt::Display, future::Future};
+
+use tokio::{
+    runtime::Runtime,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+use crate::dreamhack::{
+    catalog::Catalog,
+    challenge::handle::{Challenge, FlagResult, PageInfo},
+    error::Result,
+    vm_info::MachineInfo,
+};
+use crate::utils::{downloader::Downloaded, file_extractor::ExtractReport};
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Kinds of background work.
+///
+/// Only one task of each kind runs at a time, downloads run one per challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TaskKind {
+    Login,
+    Search,
+    /// Challenge id
+    Download(u64),
+    /// Extraction of an archive picked in the archive preview, by challenge id
+    Extract(u64),
+    /// Every page of a search, for the download queue
+    CollectPages,
+    CreateVm,
+    ExtendVm,
+    StopVm,
+    SubmitFlag,
+    SyncCatalog,
+}
+
+/// Result of a finished task, reported back to the UI thread.
+pub(crate) enum TaskResult {
+    Login(Result<()>),
+    ChallengeList(Result<(Vec<Challenge>, PageInfo)>),
+    Download(u64, anyhow::Result<Downloaded>),
+    Extracted(u64, anyhow::Result<ExtractReport>),
+    PagesCollected(Result<Vec<Challenge>>),
+    VmCreated(Box<Challenge>, Result<MachineInfo>),
+    VmExtended(Result<MachineInfo>),
+    VmStopped(Result<()>),
+    Flag(u64, Result<FlagResult>),
+    CatalogSynced(anyhow::Result<Catalog>),
+}
+
+pub(crate) struct TaskRunner {
+    runtime: Runtime,
+    sender: UnboundedSender<(u64, TaskResult)>,
+    receiver: UnboundedReceiver<(u64, TaskResult)>,
+    running: HashMap<TaskKind, (u64, JoinHandle<()>)>,
+    next_id: u64,
+    frame: usize,
+}
+
+impl TaskKind {
+    /// Login is not a user request, so Esc does not cancel it. Downloads are
+    /// cancelled one by one in the Downloads panel, extractions run to the end.
+    fn is_cancellable(&self) -> bool {
+        !matches!(
+            self,
+            TaskKind::Login | TaskKind::Download(_) | TaskKind::Extract(_)
+        )
+    }
+}
+
+impl Display for TaskKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            TaskKind::Login => write!(f, "Logging in"),
+            TaskKind::Search => write!(f, "Loading"),
+            TaskKind::Download(_) => write!(f, "Downloading"),
+            TaskKind::Extract(_) => write!(f, "Extracting"),
+            TaskKind::CollectPages => write!(f, "Collecting pages"),
+            TaskKind::CreateVm => write!(f, "Creating VM"),
+            TaskKind::ExtendVm => write!(f, "Extending VM"),
+            TaskKind::StopVm => write!(f, "Stopping VM"),
+            TaskKind::SubmitFlag => write!(f, "Submitting flag"),
+            TaskKind::SyncCatalog => write!(f, "Syncing catalog"),
+        }
+    }
+}
+
+impl TaskResult {
+    fn kind(&self) -> TaskKind {
+        match self {
+            TaskResult::Login(_) => TaskKind::Login,
+            TaskResult::ChallengeList(_) => TaskKind::Search,
+            TaskResult::Download(id, _) => TaskKind::Download(*id),
+            TaskResult::Extracted(id, _) => TaskKind::Extract(*id),
+            TaskResult::PagesCollected(_) => TaskKind::CollectPages,
+            TaskResult::VmCreated(..) => TaskKind::CreateVm,
+            TaskResult::VmExtended(_) => TaskKind::ExtendVm,
+            TaskResult::VmStopped(_) => TaskKind::StopVm,
+            TaskResult::Flag(..) => TaskKind::SubmitFlag,
+            TaskResult::CatalogSynced(_) => TaskKind::SyncCatalog,
+        }
+    }
+}
+
+impl TaskRunner {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        Self {
+            runtime: Runtime::new().expect("Failed to create tokio runtime"),
+            sender,
+            receiver,
+            running: HashMap::new(),
+            next_id: 0,
+            frame: 0,
+        }
+    }
+
+    /// Runs `task` in the background, replacing the in-flight task of the same kind.
+    pub(crate) fn spawn<F>(&mut self, kind: TaskKind, task: F)
+    where
+        F: Future<Output = TaskResult> + Send + 'static,
+    {
+        self.cancel(kind);
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let sender = self.sender.clone();
+        let handle = self.runtime.spawn(async move {
+            // The receiver lives as long as the app, so a send error only happens on exit
+            let _ = sender.send((id, task.await));
+        });
+
+        #[cfg(debug_assertions)]
+        log::info!("Spawned task {:?} ({})", kind, id);
+
+        self.running.insert(kind, (id, handle));
+    }
+
+    pub(crate) fn cancel(&mut self, kind: TaskKind) -> bool {
+        match self.running.remove(&kind) {
+            Some((_id, handle)) => {
+                #[cfg(debug_assertions)]
+                log::info!("Cancelled task {:?} ({})", kind, _id);
+
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every user request, returns whether anything was cancelled.
+    pub(crate) fn cancel_all(&mut self) -> bool {
+        let kinds = self
+            .running
+            .keys()
+            .filter(|kind| kind.is_cancellable())
+            .copied()
+            .collect::<Vec<TaskKind>>();
+
+        for kind in kinds.iter() {
+            self.cancel(*kind);
+        }
+        !kinds.is_empty()
+    }
+
+    pub(crate) fn is_running(&self, kind: TaskKind) -> bool {
+        self.running.contains_key(&kind)
+    }
+
+    /// Results of finished tasks, results of cancelled or replaced tasks are dropped.
+    pub(crate) fn poll_results(&mut self) -> Vec<TaskResult> {
+        let mut results = Vec::new();
+
+        while let Ok((id, result)) = self.receiver.try_recv() {
+            let kind = result.kind();
+            if self
+                .running
+                .get(&kind)
+                .is_some_and(|(running_id, _)| *running_id == id)
+            {
+                self.running.remove(&kind);
+                results.push(result);
+            }
+        }
+
+        results
+    }
+
+    pub(crate) fn tick(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    pub(crate) fn spinner(&self) -> &'static str {
+        SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()]
+    }
+}
+
+impl Default for TaskRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,40 @@
 #[cfg(debug_assertions)]
 use std::sync::Once;
-use std::{env, fs::File, io::Write, path::PathBuf};
+use std::{
+    env,
+    fmt::Display,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use crate::{
+    config::{ListView, Theme},
+    credentials,
     custom_widgets::{button::*, input::Input, popup::PopupItem, state_list::*},
-    dreamhack::{auth::Auth, challenge::*, options::*, vm_info::MachineInfo},
+    downloads::{ArchivePreview, DownloadQueue, QueueStatus},
+    dreamhack,
+    dreamhack::{
+        auth::Session,
+        catalog::Catalog,
+        challenge::*,
+        client::DreamhackClient,
+        error::DreamhackError,
+        options::*,
+        query::{NumberRange, QueryError, SearchQuery},
+        vm_info::MachineInfo,
+    },
     fs_tree::build_tree,
-    utils,
+    tasks::{TaskKind, TaskResult, TaskRunner},
+    utils::{
+        downloader::{self, DownloadOptions, Downloaded},
+        file_extractor::{ArchiveKind, Selection},
+    },
 };
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use color_eyre::Result;
 use crossterm::event;
 use handle::*;
-use keyring::Entry;
 use palette::tailwind::*;
 use ratatui::{crossterm::event::*, layout::*, style::*, widgets::*, DefaultTerminal, Frame};
 use tui_tree_widget::{TreeItem, TreeState};
@@ -23,7 +45,22 @@ pub(crate) const ALT_ROW_BG_COLOR: Color = SLATE.c900;
 pub(crate) const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
 pub(crate) const TEXT_FG_COLOR: Color = SLATE.c200;
 
-const OPTIONS: ([Button; 4], OptionsData, usize) = (
+/// How long the event loop waits for input before redrawing (spinner, task results)
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+/// Pause in typing after which live search runs the query
+const LIVE_SEARCH_DELAY: Duration = Duration::from_millis(300);
+
+/// Downloads of the queue that run at once
+const MAX_PARALLEL_DOWNLOADS: usize = 3;
+/// Stops queueing every page of a search after this many pages
+const MAX_COLLECTED_PAGES: u64 = 50;
+
+/// Lines the Details pane scrolls per mouse wheel step / PageUp and PageDown
+pub(crate) const DETAILS_SCROLL_STEP: i16 = 3;
+pub(crate) const DETAILS_PAGE_SIZE: i16 = 10;
+
+const OPTIONS: ([Button; 6], OptionsData, usize) = (
     [
         Button {
             label: "Category",
@@ -41,12 +78,23 @@ const OPTIONS: ([Button; 4], OptionsData, usize) = (
             label: "Order",
             state: ButtonState::Normal,
         },
+        Button {
+            label: "Type",
+            state: ButtonState::Normal,
+        },
+        Button {
+            label: "Scope",
+            state: ButtonState::Normal,
+        },
     ],
     OptionsData {
-        cat: Category::All,
-        diff: Difficulty::All,
+        cats: Vec::new(),
+        levels: None,
+        beginner: false,
         status: Status::All,
         order: Orderings::Newist,
+        kind: ChallengeType::All,
+        scope: Scope::All,
     },
     0,
 );
@@ -55,7 +103,11 @@ pub(crate) struct App {
     pub(crate) config: Option<crate::Config>,
     pub(crate) should_exit: bool,
     pub(crate) events: Events,
-    pub(crate) auth: Auth,
+    pub(crate) session: Session,
+    pub(crate) client: DreamhackClient,
+    pub(crate) tasks: TaskRunner,
+    /// Local mirror of the challenge list, `None` until the first sync
+    pub(crate) catalog: Option<Catalog>,
     pub(crate) ui_state: UIState,
     pub(crate) fs_state: FileSystemState,
     pub(crate) vm_state: VMState,
@@ -76,6 +128,40 @@ pub(crate) struct UIState {
     pub(crate) search: Input,
     pub(crate) enter_flag: Input,
     pub(crate) wargame_details_index: usize,
+    pub(crate) flag_result: Option<(u64, FlagResult)>,
+    pub(crate) error: Option<String>,
+    pub(crate) list_view: ListView,
+    /// Client-side sort of the current page, `None` keeps the server order
+    pub(crate) table_sort: Option<TableSort>,
+    pub(crate) theme: Theme,
+    pub(crate) details: DetailsState,
+    /// Searches are served from the on-disk cache only
+    pub(crate) offline: bool,
+    /// Fetch time of the shown list while it comes from the cache
+    pub(crate) stale_since: Option<DateTime<Utc>>,
+    /// Searches run over the synced catalog (always when offline)
+    pub(crate) local_search: bool,
+    /// Parsed search box of the last search, its client-side filters apply to every page
+    pub(crate) query: SearchQuery,
+    /// Shown under the search box until the input changes
+    pub(crate) query_error: Option<QueryError>,
+    /// Searches run while typing in the search box
+    pub(crate) live_search: bool,
+    /// Last edit of the search box that live search has not run yet
+    pub(crate) search_edited_at: Option<Instant>,
+    /// Shown in the footer until the next key press, like `error`
+    pub(crate) notice: Option<String>,
+    pub(crate) downloads: DownloadQueue,
+    /// Archive shown in the archive preview
+    pub(crate) preview: Option<ArchivePreview>,
+}
+
+/// Rendered description of the challenge shown in the Details pane.
+#[derive(Default)]
+pub(crate) struct DetailsState {
+    pub(crate) challenge_id: Option<u64>,
+    pub(crate) description: ratatui::text::Text<'static>,
+    pub(crate) scroll: u16,
 }
 
 pub(crate) struct FileSystemState {
@@ -86,6 +172,8 @@ pub(crate) struct FileSystemState {
 
 pub(crate) struct VMState {
     pub(crate) vm_info: MachineInfo,
+    /// Challenge the running VM belongs to
+    pub(crate) challenge: Option<Challenge>,
 }
 
 impl Default for App {
@@ -94,7 +182,10 @@ impl Default for App {
             config: None,
             events: Events::default(),
             should_exit: false,
-            auth: Auth::default(),
+            session: Session::default(),
+            client: DreamhackClient::default(),
+            tasks: TaskRunner::new(),
+            catalog: None,
             ui_state: UIState {
                 popup_state: PopupState::None,
                 cursor_state: CursorState::Search,
@@ -108,6 +199,22 @@ impl Default for App {
                 search: Input::default(),
                 enter_flag: Input::default(),
                 wargame_details_index: 0,
+                flag_result: None,
+                error: None,
+                list_view: ListView::default(),
+                table_sort: None,
+                theme: Theme::default(),
+                details: DetailsState::default(),
+                offline: false,
+                stale_since: None,
+                local_search: false,
+                query: SearchQuery::default(),
+                query_error: None,
+                live_search: false,
+                search_edited_at: None,
+                notice: None,
+                downloads: DownloadQueue::default(),
+                preview: None,
             },
             fs_state: FileSystemState {
                 workdir: env::current_dir()
@@ -118,6 +225,7 @@ impl Default for App {
             },
             vm_state: VMState {
                 vm_info: MachineInfo::default(),
+                challenge: None,
             },
         }
     }
@@ -138,12 +246,19 @@ pub(crate) enum OptionsPopupState {
     DifficultyPopup,
     StatusPopup,
     OrderPopup,
+    TypePopup,
+    ScopePopup,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OptionsPopup {
     items: Vec<OptionInfo>,
     state: OptionsPopupState,
+    /// Marks of the open popup, applied on Enter
+    draft: OptionsData,
+    /// Variants of the Category popup, the known categories and the ones seen in
+    /// loaded pages or the catalog
+    categories: Vec<Category>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -152,12 +267,18 @@ pub(crate) struct OptionInfo {
     size: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OptionsData {
-    cat: Category,
-    diff: Difficulty,
+    /// Empty for every category
+    cats: Vec<Category>,
+    /// `None` for every level
+    levels: Option<NumberRange>,
+    /// The Beginner difficulty, instead of `levels`
+    beginner: bool,
     status: Status,
     order: Orderings,
+    kind: ChallengeType,
+    scope: Scope,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -172,6 +293,8 @@ pub(crate) enum PopupState {
     None,
     Options,
     FsTreeView,
+    Downloads,
+    ArchivePreview,
 }
 
 #[derive(Default, PartialEq, Eq, Debug)]
@@ -189,13 +312,98 @@ impl Tabs {
     }
 }
 
+/// Columns of the table view of the wargame list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TableColumn {
+    Title,
+    Level,
+    Tags,
+    Solvers,
+    Status,
+    Vm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TableSort {
+    pub(crate) column: TableColumn,
+    pub(crate) descending: bool,
+}
+
+impl TableColumn {
+    pub(crate) const ALL: [TableColumn; 6] = [
+        TableColumn::Title,
+        TableColumn::Level,
+        TableColumn::Tags,
+        TableColumn::Solvers,
+        TableColumn::Status,
+        TableColumn::Vm,
+    ];
+
+    pub(crate) fn width(&self) -> Constraint {
+        match self {
+            TableColumn::Title => Constraint::Fill(3),
+            TableColumn::Level => Constraint::Length(8),
+            TableColumn::Tags => Constraint::Fill(2),
+            TableColumn::Solvers => Constraint::Length(7),
+            TableColumn::Status => Constraint::Length(9),
+            TableColumn::Vm => Constraint::Length(2),
+        }
+    }
+
+    fn compare(&self, a: &Challenge, b: &Challenge) -> std::cmp::Ordering {
+        match self {
+            TableColumn::Title => a
+                .get_info()
+                .get_title()
+                .to_lowercase()
+                .cmp(&b.get_info().get_title().to_lowercase()),
+            TableColumn::Level => Option::<u64>::from(a.get_info().get_difficulty())
+                .cmp(&Option::<u64>::from(b.get_info().get_difficulty())),
+            TableColumn::Tags => a.get_info().get_tags().cmp(b.get_info().get_tags()),
+            TableColumn::Solvers => a
+                .get_metadata()
+                .get_stats()
+                .solvers
+                .cmp(&b.get_metadata().get_stats().solvers),
+            TableColumn::Status => {
+                let status = |challenge: &Challenge| {
+                    let flags = challenge.get_metadata().get_flags();
+                    (flags.is_completed(), flags.is_attempted())
+                };
+                status(a).cmp(&status(b))
+            }
+            TableColumn::Vm => a
+                .get_metadata()
+                .get_flags()
+                .needs_vm()
+                .cmp(&b.get_metadata().get_flags().needs_vm()),
+        }
+    }
+}
+
+impl Display for TableColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            TableColumn::Title => write!(f, "Title"),
+            TableColumn::Level => write!(f, "Level"),
+            TableColumn::Tags => write!(f, "Tags"),
+            TableColumn::Solvers => write!(f, "Solvers"),
+            TableColumn::Status => write!(f, "Status"),
+            TableColumn::Vm => write!(f, "VM"),
+        }
+    }
+}
+
 impl Default for OptionsData {
     fn default() -> Self {
         Self {
-            cat: Category::All,
-            diff: Difficulty::All,
+            cats: Vec::new(),
+            levels: None,
+            beginner: false,
             status: Status::All,
             order: Orderings::Newist,
+            kind: ChallengeType::All,
+            scope: Scope::All,
         }
     }
 }
@@ -221,26 +429,59 @@ impl OptionsPopup {
         &self.items
     }
 
-    pub(crate) fn get_mut_items(&mut self) -> &mut Vec<OptionInfo> {
-        &mut self.items
-    }
-
     pub(crate) fn get_state(&self) -> OptionsPopupState {
         self.state
     }
 
-    pub(crate) fn set_state(&mut self, state: OptionsPopupState) {
-        self.state = state;
+    pub(crate) fn get_categories(&self) -> &[Category] {
+        &self.categories
+    }
+
+    /// Category under the cursor of the Category popup.
+    fn get_category(&self, index: usize) -> Category {
+        self.categories.get(index).cloned().unwrap_or(Category::All)
+    }
+
+    /// Which variants of the open popup are marked, `None` for single choice popups.
+    pub(crate) fn get_marks(&self) -> Option<Vec<bool>> {
+        match self.state {
+            OptionsPopupState::CategoryPopup => Some(
+                self.categories
+                    .iter()
+                    .map(|category| match category {
+                        Category::All => self.draft.cats.is_empty(),
+                        category => self.draft.cats.contains(category),
+                    })
+                    .collect(),
+            ),
+            OptionsPopupState::DifficultyPopup => Some(
+                Difficulty::variants()
+                    .into_iter()
+                    .map(|difficulty| match difficulty {
+                        Difficulty::All => self.draft.levels.is_none() && !self.draft.beginner,
+                        Difficulty::Beginner => self.draft.beginner,
+                        difficulty => Option::<u64>::from(difficulty).is_some_and(|level| {
+                            self.draft.levels.is_some_and(|l| l.contains(level))
+                        }),
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
     }
 }
 
 impl OptionsData {
-    pub(crate) fn get_category(&self) -> &Category {
-        &self.cat
+    pub(crate) fn get_categories(&self) -> &[Category] {
+        &self.cats
+    }
+
+    pub(crate) fn get_levels(&self) -> Option<NumberRange> {
+        self.levels
     }
 
-    pub(crate) fn get_difficulty(&self) -> &Difficulty {
-        &self.diff
+    pub(crate) fn is_beginner(&self) -> bool {
+        self.beginner
     }
 
     pub(crate) fn get_status(&self) -> &Status {
@@ -250,6 +491,55 @@ impl OptionsData {
     pub(crate) fn get_order(&self) -> &Orderings {
         &self.order
     }
+
+    pub(crate) fn get_type(&self) -> &ChallengeType {
+        &self.kind
+    }
+
+    pub(crate) fn get_scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// All clears the marks, otherwise `category` is marked or unmarked.
+    fn toggle_category(&mut self, category: Category) {
+        match self.cats.iter().position(|cat| *cat == category) {
+            _ if category == Category::All => self.cats.clear(),
+            Some(i) => {
+                self.cats.remove(i);
+            }
+            None => {
+                self.cats.push(category);
+                // Keep the order of the popup
+                self.cats.sort_by_key(Category::sort_key);
+            }
+        }
+    }
+
+    /// The first mark picks a single level, the second one extends it to a range.
+    /// Marking once more starts over. All clears the range, Beginner replaces it.
+    fn mark_level(&mut self, difficulty: Difficulty) {
+        self.beginner = difficulty == Difficulty::Beginner;
+        let Some(level) = Option::<u64>::from(difficulty) else {
+            self.levels = None;
+            return;
+        };
+        self.levels = match self.levels.and_then(|range| range.exact()) {
+            Some(start) => Some(NumberRange::new(start, level)),
+            None => Some(NumberRange::new(level, level)),
+        };
+    }
+
+    /// Filters the API can not apply, a range of levels.
+    pub(crate) fn matches(&self, challenge: &Challenge) -> bool {
+        match (
+            self.levels,
+            Option::<u64>::from(challenge.get_info().get_difficulty()),
+        ) {
+            (Some(range), Some(level)) => range.contains(level),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
 }
 
 impl Options {
@@ -283,16 +573,85 @@ impl Options {
         &self.popup
     }
 
-    pub(crate) fn get_mut_popup(&mut self) -> &mut OptionsPopup {
-        &mut self.popup
-    }
-
     pub(crate) fn get_selected_index(&self) -> usize {
         self.buttons
             .iter()
             .position(|btn| btn.get_state() == ButtonState::Selected)
             .unwrap_or(0)
     }
+
+    /// Opens the popup of the option button at `index`, with the current values marked.
+    pub(crate) fn open_popup(&mut self, index: usize) {
+        let state = match index {
+            0 => OptionsPopupState::CategoryPopup,
+            1 => OptionsPopupState::DifficultyPopup,
+            2 => OptionsPopupState::StatusPopup,
+            3 => OptionsPopupState::OrderPopup,
+            4 => OptionsPopupState::TypePopup,
+            5 => OptionsPopupState::ScopePopup,
+            _ => return,
+        };
+        self.popup.state = state;
+        self.popup.draft = self.items.clone();
+    }
+
+    pub(crate) fn close_popup(&mut self) {
+        self.popup.state = OptionsPopupState::None;
+    }
+
+    /// Adds the categories of `challenges` the Category popup does not list yet.
+    ///
+    /// The first tag of a challenge is its category, the others are topics (e.g. `heap`).
+    pub(crate) fn add_categories<'a>(
+        &mut self,
+        challenges: impl IntoIterator<Item = &'a Challenge>,
+    ) {
+        let popup = &mut self.popup;
+        let len = popup.categories.len();
+        for challenge in challenges {
+            let Some(tag) = challenge.get_info().get_tags().first() else {
+                continue;
+            };
+            let category = Category::new(tag);
+            if category != Category::All && !popup.categories.contains(&category) {
+                popup.categories.push(category);
+            }
+        }
+        if popup.categories.len() == len {
+            return;
+        }
+
+        popup.categories.sort_by_key(Category::sort_key);
+        if let Some(item) = popup.items.first_mut() {
+            item.size = popup.categories.len();
+        }
+    }
+
+    /// Moves the cursor of the open popup by `delta`, within its variants.
+    pub(crate) fn move_popup_cursor(&mut self, delta: isize) {
+        if let Some(item) = self.popup.items.get_mut(self.buttons_index) {
+            let last = item.size.saturating_sub(1);
+            item.index = item.index.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Marks the variant under the cursor, in popups that take more than one.
+    pub(crate) fn mark_popup_item(&mut self) {
+        let Some(item) = self.popup.items.get(self.buttons_index) else {
+            return;
+        };
+        match self.popup.state {
+            OptionsPopupState::CategoryPopup => {
+                let category = self.popup.get_category(item.index);
+                self.popup.draft.toggle_category(category)
+            }
+            OptionsPopupState::DifficultyPopup => self
+                .popup
+                .draft
+                .mark_level(Difficulty::from_index(item.index)),
+            _ => {}
+        }
+    }
 }
 
 impl Default for Options {
@@ -304,6 +663,8 @@ impl Default for Options {
             popup: OptionsPopup {
                 items: Vec::new(),
                 state: OptionsPopupState::None,
+                draft: OptionsData::default(),
+                categories: Category::defaults(),
             },
         }
     }
@@ -314,22 +675,21 @@ impl App {
         mut self,
         terminal: &mut DefaultTerminal,
         config: crate::Config,
-        email_entry: Entry,
-        password_entry: Entry,
+        email: &str,
+        password: &str,
     ) -> Result<()> {
         // let mut last_cursor_toggle = Instant::now();
-        let mut request = RequestChallengeList::new();
-        (self.ui_state.challenges.items, self.ui_state.current_page) =
-            request.send_request().unwrap_or_default();
+        self.client = DreamhackClient::new(config.api_base_url.as_deref());
+
         self.ui_state.options = Options {
             buttons: OPTIONS.0.into_iter().collect(),
             buttons_index: OPTIONS.2,
-            items: OPTIONS.1,
+            items: OPTIONS.1.clone(),
             popup: OptionsPopup {
                 items: vec![
                     OptionInfo {
                         index: 0,
-                        size: Category::variants().len(),
+                        size: Category::defaults().len(),
                     },
                     OptionInfo {
                         index: 0,
@@ -343,27 +703,98 @@ impl App {
                         index: 0,
                         size: Orderings::variants().len(),
                     },
+                    OptionInfo {
+                        index: 0,
+                        size: ChallengeType::variants().len(),
+                    },
+                    OptionInfo {
+                        index: 0,
+                        size: Scope::variants().len(),
+                    },
                 ],
                 state: OptionsPopupState::None,
+                draft: OPTIONS.1,
+                categories: Category::defaults(),
             },
         };
 
+        self.ui_state.list_view = config.list_view;
+        self.ui_state.theme = config.theme.resolve();
+        self.ui_state.offline = config.offline;
+        self.ui_state.local_search = config.local_search;
+        self.ui_state.live_search = config.live_search;
         self.config = Some(config);
+        self.catalog = Catalog::load();
+        if let Some(catalog) = &self.catalog {
+            self.ui_state
+                .options
+                .add_categories(catalog.get_challenges());
+        }
         self.fs_state.tree_items = build_tree(&self.fs_state.workdir)
             .context("Failed to build tree")
             .unwrap();
 
-        let email = String::from_utf8_lossy(&email_entry.get_secret().unwrap()).into_owned();
-        let password = password_entry.get_password().unwrap();
-        self.auth = Auth::send_login(&email, &password, false).unwrap();
+        // Reuse the session of the last run, it is renewed on the first 401/403
+        self.session = Session::new(email, password, credentials::load_session())
+            .with_login_hook(credentials::save_session);
+
+        self.spawn_requests(vec![RequestChallengeList::new()]);
+        self.spawn_login();
 
         while !self.should_exit {
             terminal.draw(|frame| self.draw(frame))?;
+            // Mouse events are handled while rendering, so handle each one only once
+            self.events.mouse = None;
+
+            self.handle_task_results();
+            self.run_live_search();
+
+            if !event::poll(TICK_RATE)? {
+                self.tasks.tick();
+                continue;
+            }
 
             match event::read()? {
                 Event::Key(key) => {
+                    // Esc cancels in-flight requests before anything else
+                    if key.code == KeyCode::Esc
+                        && key.kind == KeyEventKind::Press
+                        && self.ui_state.popup_state == PopupState::None
+                        && self.tasks.cancel_all()
+                    {
+                        continue;
+                    }
+
                     match (key.code, key.modifiers) {
                         (KeyCode::Tab, _) => self.next_tab(),
+                        (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                            self.toggle_offline();
+                            continue;
+                        }
+                        (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
+                            self.toggle_local_search();
+                            continue;
+                        }
+                        (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                            self.handle_sync_catalog();
+                            continue;
+                        }
+                        (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+                            self.toggle_live_search();
+                            continue;
+                        }
+                        (KeyCode::Char('d'), KeyModifiers::CONTROL)
+                            if matches!(
+                                self.ui_state.popup_state,
+                                PopupState::None | PopupState::Downloads
+                            ) =>
+                        {
+                            self.ui_state.popup_state = match self.ui_state.popup_state {
+                                PopupState::Downloads => PopupState::None,
+                                _ => PopupState::Downloads,
+                            };
+                            continue;
+                        }
                         (KeyCode::Char('w'), KeyModifiers::CONTROL)
                             if self.ui_state.popup_state == PopupState::None =>
                         {
@@ -418,8 +849,8 @@ impl App {
             });
         }
 
-        App::render_header(header_area, frame);
-        App::render_footer(footer_area, frame);
+        self.render_header(header_area, frame);
+        self.render_footer(footer_area, frame);
         self.render_search(search_area, frame);
         self.render_options(options_area, frame);
         self.render_options_value(show_options, frame);
@@ -430,6 +861,8 @@ impl App {
         match self.ui_state.popup_state {
             PopupState::Options => self.render_options_popup(frame),
             PopupState::FsTreeView => self.render_fs_tree_view_popup(frame),
+            PopupState::Downloads => self.render_downloads_popup(frame),
+            PopupState::ArchivePreview => self.render_archive_preview_popup(frame),
             PopupState::None => {}
         }
     }
@@ -437,21 +870,343 @@ impl App {
 
 impl App {
     pub(crate) fn next_page(&mut self) {
-        self.ui_state.current_page.next_page();
+        let mut page = self.ui_state.current_page.clone();
+        page.next_page();
 
-        let mut request = RequestChallengeList::new();
-        request.set_page(self.ui_state.current_page.get_page_idx());
-        (self.ui_state.challenges.items, self.ui_state.current_page) =
-            request.send_request().unwrap();
+        let requests = self.build_requests(page.get_page_idx());
+        self.spawn_requests(requests);
     }
 
     pub(crate) fn previous_page(&mut self) {
-        self.ui_state.current_page.previous_page();
+        let mut page = self.ui_state.current_page.clone();
+        page.previous_page();
+
+        let requests = self.build_requests(page.get_page_idx());
+        self.spawn_requests(requests);
+    }
+
+    /// Runs the query in effect again on the current page, e.g. after logging in.
+    /// The search box is left alone, it may hold input that is still being typed.
+    pub(crate) fn refresh_search(&mut self) {
+        let page = self.ui_state.current_page.get_page_idx().max(1);
+        let requests = self.build_requests(page);
+        self.spawn_requests(requests);
+    }
+
+    /// Logs in in the background, unless the session is still valid or offline.
+    fn spawn_login(&mut self) {
+        if self.session.is_logged_in() || self.ui_state.offline {
+            return;
+        }
+
+        let client = self.client.clone();
+        let session = self.session.clone();
+        self.tasks.spawn(TaskKind::Login, async move {
+            TaskResult::Login(session.login(&client).await.map(|_| ()))
+        });
+    }
+
+    /// Going back online refreshes the list and logs in if needed.
+    pub(crate) fn toggle_offline(&mut self) {
+        self.ui_state.offline = !self.ui_state.offline;
+        if self.ui_state.offline {
+            self.tasks.cancel(TaskKind::Login);
+        } else {
+            self.spawn_login();
+        }
+        self.refresh_search();
+    }
+
+    pub(crate) fn toggle_local_search(&mut self) {
+        if self.catalog.is_none() {
+            self.show_error("No catalog yet, press Ctrl+S to sync it");
+            return;
+        }
+        self.ui_state.local_search = !self.ui_state.local_search;
+        self.refresh_search();
+    }
 
-        let mut request = RequestChallengeList::new();
-        request.set_page(self.ui_state.current_page.get_page_idx());
-        (self.ui_state.challenges.items, self.ui_state.current_page) =
-            request.send_request().unwrap();
+    pub(crate) fn toggle_live_search(&mut self) {
+        self.ui_state.live_search = !self.ui_state.live_search;
+        self.ui_state.search_edited_at = None;
+    }
+
+    /// Catalog the searches run over, if local search is on.
+    pub(crate) fn get_local_catalog(&self) -> Option<&Catalog> {
+        match self.ui_state.local_search || self.ui_state.offline {
+            true => self.catalog.as_ref(),
+            false => None,
+        }
+    }
+
+    /// Updates the catalog in the background, the first sync walks every page.
+    pub(crate) fn handle_sync_catalog(&mut self) {
+        if self.ui_state.offline {
+            self.show_error("Offline, press Ctrl+O to go online and sync");
+            return;
+        }
+        if self.tasks.is_running(TaskKind::SyncCatalog) {
+            return;
+        }
+
+        let client = self.client.clone();
+        let session = self.session.clone();
+        self.tasks.spawn(TaskKind::SyncCatalog, async move {
+            let result = async {
+                let mut catalog = Catalog::load().unwrap_or_default();
+                let session = session.is_logged_in().then_some(&session);
+                catalog.sync(&client, session, false).await?;
+                catalog.save()?;
+                Ok(catalog)
+            };
+            TaskResult::CatalogSynced(result.await)
+        });
+    }
+
+    /// Shows the cached results of the same requests right away, then refreshes
+    /// them in the background, replacing any in-flight search.
+    ///
+    /// The pages of all `requests` are merged into one. Offline, only the cache is
+    /// used. Local searches never hit the API.
+    pub(crate) fn spawn_requests(&mut self, requests: Vec<RequestChallengeList>) {
+        let ordering = requests
+            .first()
+            .map(|request| request.get_ordering())
+            .unwrap_or(Orderings::Newist);
+
+        if let Some(catalog) = self.get_local_catalog() {
+            let pages = requests
+                .iter()
+                .map(|request| catalog.search(request))
+                .collect();
+            let (challenges, page_info) = merge_pages(pages, ordering);
+            self.tasks.cancel(TaskKind::Search);
+            self.show_challenges(challenges, page_info);
+            self.ui_state.stale_since = None;
+            return;
+        }
+
+        let logged_in = self.session.is_logged_in();
+        let cached = requests
+            .iter()
+            .map(|request| request.load_cached(&self.client, logged_in))
+            .collect::<Option<Vec<_>>>()
+            .map(|cached| {
+                // The oldest of the pages
+                let fetched_at = cached.iter().map(|(_, _, fetched_at)| *fetched_at).min();
+                let pages = cached
+                    .into_iter()
+                    .map(|(challenges, page_info, _)| (challenges, page_info))
+                    .collect();
+                let (challenges, page_info) = merge_pages(pages, ordering);
+                (challenges, page_info, fetched_at.unwrap_or_else(Utc::now))
+            });
+        match cached {
+            Some((challenges, page_info, fetched_at)) => {
+                self.show_challenges(challenges, page_info);
+                self.ui_state.stale_since = Some(fetched_at);
+            }
+            None if self.ui_state.offline => {
+                self.show_challenges(Vec::new(), PageInfo::default());
+                self.ui_state.stale_since = None;
+                self.show_error(DreamhackError::NotCached);
+            }
+            None => {}
+        }
+
+        if self.ui_state.offline {
+            self.tasks.cancel(TaskKind::Search);
+            return;
+        }
+
+        let client = self.client.clone();
+        let session = self.session.clone();
+        self.tasks.spawn(TaskKind::Search, async move {
+            let client = &client;
+            let merged = requests.len() > 1;
+            let mut pages = Vec::new();
+            for request in requests.iter() {
+                match fetch_page(client, &session, request).await {
+                    Ok(page) => pages.push(page),
+                    // One of the merged lists has fewer pages than the others
+                    Err(DreamhackError::Status { status, .. })
+                        if merged && status == reqwest::StatusCode::NOT_FOUND => {}
+                    Err(e) => return TaskResult::ChallengeList(Err(e)),
+                }
+            }
+            TaskResult::ChallengeList(Ok(merge_pages(pages, ordering)))
+        });
+    }
+
+    fn handle_task_results(&mut self) {
+        for result in self.tasks.poll_results() {
+            match result {
+                // Reload the list, so it shows the solved/attempted flags of the user
+                TaskResult::Login(Ok(())) => self.refresh_search(),
+                TaskResult::Login(Err(e)) => self.show_error(format!("Login failed: {}", e)),
+                TaskResult::ChallengeList(result) => self.apply_request_result(result),
+                TaskResult::Download(id, result) => {
+                    let unextracted = match &result {
+                        Ok(Downloaded::Saved(path, None)) => Some(path.clone()),
+                        _ => None,
+                    };
+                    self.ui_state.downloads.finish(id, result);
+                    self.start_queued_downloads();
+
+                    let preview_archive = self.config.as_ref().is_some_and(|c| c.preview_archive);
+                    if let Some(archive) = unextracted.filter(|_| preview_archive) {
+                        self.preview_or_extract(id, archive);
+                    }
+                }
+                TaskResult::Extracted(id, Ok(report)) => {
+                    self.ui_state.notice = Some(match report.is_empty() {
+                        true => "Extracted".to_string(),
+                        false => format!("Extracted with {}, Ctrl+D shows the report", report),
+                    });
+                    self.ui_state.downloads.extracted(id, report);
+                }
+                TaskResult::Extracted(_, Err(e)) => {
+                    self.show_error(format!("Failed to extract: {:#}", e))
+                }
+                TaskResult::PagesCollected(Ok(mut challenges)) => {
+                    challenges.retain(|challenge| {
+                        self.ui_state.options.items.matches(challenge)
+                            && self.ui_state.query.matches(challenge)
+                    });
+                    self.queue_downloads(challenges);
+                }
+                TaskResult::PagesCollected(Err(e)) => {
+                    self.show_error(format!("Failed to collect pages: {}", e))
+                }
+                TaskResult::VmCreated(challenge, Ok(vm_info)) => {
+                    self.vm_state.vm_info = vm_info;
+                    self.vm_state.challenge = Some(*challenge);
+                }
+                TaskResult::VmCreated(_, Err(e)) => {
+                    self.show_error(format!("Failed to create VM: {}", e))
+                }
+                TaskResult::VmExtended(Ok(vm_info)) => self.vm_state.vm_info = vm_info,
+                TaskResult::VmExtended(Err(e)) => {
+                    self.show_error(format!("Failed to extend VM: {}", e))
+                }
+                TaskResult::VmStopped(Ok(())) => {
+                    self.vm_state.vm_info = MachineInfo::default();
+                    self.vm_state.challenge = None;
+                }
+                TaskResult::VmStopped(Err(e)) => {
+                    self.show_error(format!("Failed to stop VM: {}", e))
+                }
+                TaskResult::Flag(id, Ok(result)) => self.apply_flag_result(id, result),
+                TaskResult::Flag(_, Err(e)) => {
+                    self.show_error(format!("Failed to submit flag: {}", e))
+                }
+                TaskResult::CatalogSynced(Ok(catalog)) => {
+                    self.ui_state
+                        .options
+                        .add_categories(catalog.get_challenges());
+                    self.catalog = Some(catalog);
+                    if self.get_local_catalog().is_some() {
+                        self.refresh_search();
+                    }
+                }
+                TaskResult::CatalogSynced(Err(e)) => {
+                    self.show_error(format!("Failed to sync catalog: {:#}", e))
+                }
+            }
+        }
+    }
+
+    fn apply_request_result(
+        &mut self,
+        result: dreamhack::error::Result<(Vec<Challenge>, PageInfo)>,
+    ) {
+        match result {
+            Ok((challenges, page_info)) => {
+                self.show_challenges(challenges, page_info);
+                self.ui_state.stale_since = None;
+            }
+            // The cached list, if any, stays with its stale marker
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    fn show_challenges(&mut self, mut challenges: Vec<Challenge>, page_info: PageInfo) {
+        self.ui_state.options.add_categories(&challenges);
+        challenges.retain(|challenge| {
+            self.ui_state.options.items.matches(challenge) && self.ui_state.query.matches(challenge)
+        });
+
+        let list = &mut self.ui_state.challenges;
+        // The new page may be shorter than the selected index
+        if list.state.selected().is_some_and(|i| i >= challenges.len()) {
+            list.state.select(None);
+        }
+        list.items = challenges;
+        self.ui_state.current_page = page_info;
+        self.sort_challenges();
+    }
+
+    pub(crate) fn scroll_details(&mut self, lines: i16) {
+        let details = &mut self.ui_state.details;
+        details.scroll = details.scroll.saturating_add_signed(lines);
+    }
+
+    pub(crate) fn toggle_list_view(&mut self) {
+        self.ui_state.list_view = match self.ui_state.list_view {
+            ListView::List => ListView::Table,
+            ListView::Table => ListView::List,
+        };
+    }
+
+    /// Sorts by `column`, sorting by the same column again reverses the order.
+    pub(crate) fn sort_by(&mut self, column: TableColumn) {
+        self.ui_state.table_sort = match self.ui_state.table_sort {
+            Some(sort) if sort.column == column => Some(TableSort {
+                column,
+                descending: !sort.descending,
+            }),
+            _ => Some(TableSort {
+                column,
+                descending: false,
+            }),
+        };
+        self.sort_challenges();
+    }
+
+    /// Applies `table_sort` to the current page, keeping the selected challenge selected.
+    fn sort_challenges(&mut self) {
+        let Some(sort) = self.ui_state.table_sort else {
+            return;
+        };
+
+        let challenges = &mut self.ui_state.challenges;
+        let selected_id = challenges
+            .state
+            .selected()
+            .and_then(|i| challenges.items.get(i))
+            .map(|challenge| challenge.get_id());
+
+        // Stable sort, so equal rows keep the server order
+        challenges.items.sort_by(|a, b| match sort.descending {
+            true => sort.column.compare(b, a),
+            false => sort.column.compare(a, b),
+        });
+
+        if let Some(id) = selected_id {
+            let index = challenges
+                .items
+                .iter()
+                .position(|challenge| challenge.get_id() == id);
+            challenges.state.select(index);
+        }
+    }
+
+    /// Shows an error in the footer until the next key press.
+    pub(crate) fn show_error(&mut self, error: impl std::fmt::Display) {
+        #[cfg(debug_assertions)]
+        log::error!("{}", error);
+
+        self.ui_state.error = Some(error.to_string());
     }
 }
 
@@ -470,41 +1225,34 @@ impl App {
 }
 
 impl App {
+    /// Applies the marks of the open popup. Without any, the variant under the
+    /// cursor is picked, like in the single choice popups.
     pub(crate) fn apply_popup_selection(&mut self) {
-        match self.ui_state.options.popup.state {
+        let options = &mut self.ui_state.options;
+        let index = options.popup.items[options.buttons_index].index;
+        let category = options.popup.get_category(index);
+        let draft = &mut options.popup.draft;
+        match options.popup.state {
             OptionsPopupState::CategoryPopup => {
-                self.ui_state.options.items.cat = Category::from_index(
-                    self.ui_state.options.popup.items[self.ui_state.options.buttons_index].index,
-                )
+                if draft.cats.is_empty() {
+                    draft.toggle_category(category);
+                }
+                options.items.cats = draft.cats.clone();
             }
             OptionsPopupState::DifficultyPopup => {
-                self.ui_state.options.items.diff = Difficulty::from_index(
-                    self.ui_state.options.popup.items[self.ui_state.options.buttons_index].index,
-                )
-            }
-            OptionsPopupState::StatusPopup => {
-                self.ui_state.options.items.status = Status::from_index(
-                    self.ui_state.options.popup.items[self.ui_state.options.buttons_index].index,
-                )
-            }
-            OptionsPopupState::OrderPopup => {
-                self.ui_state.options.items.order = Orderings::from_index(
-                    self.ui_state.options.popup.items[self.ui_state.options.buttons_index].index,
-                )
+                if draft.levels.is_none() && !draft.beginner {
+                    draft.mark_level(Difficulty::from_index(index));
+                }
+                options.items.levels = draft.levels;
+                options.items.beginner = draft.beginner;
             }
+            OptionsPopupState::StatusPopup => options.items.status = Status::from_index(index),
+            OptionsPopupState::OrderPopup => options.items.order = Orderings::from_index(index),
+            OptionsPopupState::TypePopup => options.items.kind = ChallengeType::from_index(index),
+            OptionsPopupState::ScopePopup => options.items.scope = Scope::from_index(index),
             OptionsPopupState::None => {}
         }
     }
-
-    pub(crate) fn get_popup_items_length(&self) -> usize {
-        match self.ui_state.options.popup.state {
-            OptionsPopupState::CategoryPopup => Category::variants().len(),
-            OptionsPopupState::DifficultyPopup => Difficulty::variants().len(),
-            OptionsPopupState::StatusPopup => Status::variants().len(),
-            OptionsPopupState::OrderPopup => Orderings::variants().len(),
-            OptionsPopupState::None => 0,
-        }
-    }
 }
 
 impl<'a> From<&'a Challenge> for ListItem<'a> {
@@ -515,86 +1263,414 @@ impl<'a> From<&'a Challenge> for ListItem<'a> {
 }
 
 impl App {
+    /// Parse errors of the search box are shown under it instead of searching.
     pub(crate) fn start_search(&mut self) {
-        let mut request = RequestChallengeList::new();
-        request.set_search(self.ui_state.search.input.clone());
-        request.set_category(self.ui_state.options.items.cat);
-        request.set_difficulty(self.ui_state.options.items.diff);
-        request.set_status(self.ui_state.options.items.status);
-        request.set_ordering(self.ui_state.options.items.order);
-
-        (self.ui_state.challenges.items, self.ui_state.current_page) = request
-            .send_request()
-            .context("Failed to send request")
-            .unwrap();
+        self.ui_state.search_edited_at = None;
+        match SearchQuery::parse(&self.ui_state.search.input) {
+            Ok(query) => {
+                self.ui_state.query = query;
+                self.ui_state.query_error = None;
+            }
+            Err(e) => {
+                self.ui_state.query_error = Some(e);
+                return;
+            }
+        }
+
+        let requests = self.build_requests(1);
+        self.spawn_requests(requests);
 
         self.ui_state.search.reset_cursor();
     }
 
-    fn start_download(&mut self, path: &str) {
-        if let Some(selected_index) = self.ui_state.challenges.state.selected() {
-            let challenge = &self.ui_state.challenges.items[selected_index];
-            let challenge_data = challenge.download_challenge();
-            let f = File::create_new(PathBuf::from(path));
+    /// Remembers an edit of the search box, the results of older input are dropped.
+    pub(crate) fn mark_search_edited(&mut self) {
+        if !self.ui_state.live_search {
+            return;
+        }
+        self.ui_state.search_edited_at = Some(Instant::now());
+        self.tasks.cancel(TaskKind::Search);
+    }
 
-            match f {
-                Ok(mut file) => {
-                    file.write_all(challenge_data.as_slice())
-                        .context("Failed to write file")
-                        .unwrap();
-                }
-                #[allow(unused_variables)]
-                Err(e) => {
-                    #[cfg(debug_assertions)]
-                    log::error!("Failed to create file: {:?}", e);
-                }
-            }
+    /// Searches once typing paused for `LIVE_SEARCH_DELAY`, the input is kept.
+    ///
+    /// Input with a mistake (e.g. a half typed `cat:`) is skipped silently, Enter
+    /// shows the error.
+    fn run_live_search(&mut self) {
+        match self.ui_state.search_edited_at {
+            Some(edited_at) if edited_at.elapsed() >= LIVE_SEARCH_DELAY => {}
+            _ => return,
         }
+        self.ui_state.search_edited_at = None;
+
+        let Ok(query) = SearchQuery::parse(&self.ui_state.search.input) else {
+            return;
+        };
+        self.ui_state.query = query;
+        let requests = self.build_requests(1);
+        self.spawn_requests(requests);
     }
 
+    /// Requests of the option buttons, overridden by the filters of the search box.
+    ///
+    /// Each marked category is a request of its own. A range of levels is filtered
+    /// on the client, only a single level is sent to the API.
+    fn build_requests(&self, page: u64) -> Vec<RequestChallengeList> {
+        let items = &self.ui_state.options.items;
+        let categories = match self.ui_state.query.get_category() {
+            None if !items.cats.is_empty() => items.cats.clone(),
+            // `cat:` of the search box replaces the marked categories
+            _ => vec![Category::All],
+        };
+        let difficulty = match items.beginner {
+            true => Difficulty::Beginner,
+            false => items
+                .levels
+                .and_then(|levels| levels.exact())
+                .map(Difficulty::from)
+                .unwrap_or(Difficulty::All),
+        };
+
+        categories
+            .into_iter()
+            .map(|category| {
+                let mut request = RequestChallengeList::new();
+                request.set_page(page);
+                request.set_category(category);
+                request.set_difficulty(difficulty);
+                request.set_status(items.status);
+                request.set_ordering(items.order);
+                request.set_type(items.kind);
+                request.set_scope(items.scope);
+                self.ui_state.query.apply(&mut request);
+                request
+            })
+            .collect()
+    }
+
+    /// Queues the selected challenge.
     pub(crate) fn handle_download_file(&mut self) {
         if let Some(selected_item) = self.ui_state.challenges.state.selected() {
-            let workdir = self
-                .fs_state
-                .workdir
-                .to_str()
-                .context("Failed to get workdir")
-                .unwrap()
-                .to_owned();
-
-            let repository = self.ui_state.challenges.items[selected_item]
-                .get_metadata()
-                .get_repository()
-                .to_owned();
+            let challenge = self.ui_state.challenges.items[selected_item].clone();
+            self.queue_downloads(vec![challenge]);
+        }
+    }
 
-            let file_path = format!("{}/{}.zip", workdir, repository);
+    /// Queues every challenge of the current page.
+    pub(crate) fn queue_page(&mut self) {
+        let challenges = self.ui_state.challenges.items.clone();
+        self.queue_downloads(challenges);
+    }
 
-            self.start_download(&file_path);
+    /// Queues every page of the last search, the pages are fetched in the background.
+    pub(crate) fn queue_all_pages(&mut self) {
+        if self.ui_state.offline {
+            self.show_error("Offline, press Ctrl+O to go online and download");
+            return;
+        }
 
-            if self.config.as_ref().unwrap().extract_chall_file {
-                utils::file_extractor::extract_file(
-                    PathBuf::from(&file_path),
-                    PathBuf::from(&workdir),
-                    &repository,
-                )
-                .unwrap();
+        let requests = self.build_requests(1);
+        if let Some(catalog) = self.get_local_catalog() {
+            // The catalog has every result on a single page
+            let mut challenges = requests
+                .iter()
+                .flat_map(|request| catalog.search(request).0)
+                .collect::<Vec<Challenge>>();
+            challenges.retain(|challenge| {
+                self.ui_state.options.items.matches(challenge)
+                    && self.ui_state.query.matches(challenge)
+            });
+            self.queue_downloads(challenges);
+            return;
+        }
+
+        let client = self.client.clone();
+        let session = self.session.clone();
+        self.tasks.spawn(TaskKind::CollectPages, async move {
+            let mut challenges = Vec::new();
+            for mut request in requests {
+                for page in 1..=MAX_COLLECTED_PAGES {
+                    request.set_page(page);
+                    match fetch_page(&client, &session, &request).await {
+                        Ok((page_challenges, page_info)) => {
+                            challenges.extend(page_challenges);
+                            if !page_info.has_next() {
+                                break;
+                            }
+                        }
+                        // Past the last page
+                        Err(DreamhackError::Status { status, .. })
+                            if status == reqwest::StatusCode::NOT_FOUND =>
+                        {
+                            break
+                        }
+                        Err(e) => return TaskResult::PagesCollected(Err(e)),
+                    }
+                }
             }
+            TaskResult::PagesCollected(Ok(challenges))
+        });
+    }
 
-            if !self.config.as_ref().unwrap().keep_chall_file {
-                std::fs::remove_file(file_path)
-                    .context("Failed to remove file")
-                    .unwrap();
+    pub(crate) fn queue_downloads(&mut self, challenges: Vec<Challenge>) {
+        if self.ui_state.offline {
+            self.show_error("Offline, press Ctrl+O to go online and download");
+            return;
+        }
+
+        let added = self.ui_state.downloads.push(challenges);
+        self.ui_state.notice = Some(match added {
+            0 => "Already in the download queue".to_string(),
+            1 => "Queued 1 download, Ctrl+D shows the queue".to_string(),
+            added => format!("Queued {} downloads, Ctrl+D shows the queue", added),
+        });
+        self.start_queued_downloads();
+    }
+
+    /// Starts queued downloads, at most `MAX_PARALLEL_DOWNLOADS` at once.
+    pub(crate) fn start_queued_downloads(&mut self) {
+        let mut options = DownloadOptions::from(self.config.as_ref().unwrap());
+        // Extracted from the archive preview instead
+        if self.config.as_ref().unwrap().preview_archive {
+            options.extract = false;
+            options.keep = true;
+        }
+
+        for (challenge, progress) in self.ui_state.downloads.start_next(MAX_PARALLEL_DOWNLOADS) {
+            let client = self.client.clone();
+            let workdir = self.fs_state.workdir.clone();
+            let id = challenge.get_id();
+
+            self.tasks.spawn(TaskKind::Download(id), async move {
+                TaskResult::Download(
+                    id,
+                    downloader::download_file(&client, &challenge, workdir, options, &progress)
+                        .await,
+                )
+            });
+        }
+    }
+
+    /// Previews the archive of the selected download, if it is still there.
+    pub(crate) fn preview_selected_download(&mut self) {
+        let Some(item) = self.ui_state.downloads.get_selected() else {
+            return;
+        };
+        // A skipped download previews the earlier archive
+        let (QueueStatus::Saved(archive, _) | QueueStatus::Skipped(archive)) = item.get_status()
+        else {
+            return;
+        };
+
+        if !archive.is_file() {
+            self.show_error(format!(
+                "{} was removed after extracting, set keep_chall_file to preview it",
+                archive.display()
+            ));
+            return;
+        }
+        let (id, archive) = (item.get_challenge().get_id(), archive.clone());
+        self.open_archive_preview(id, archive);
+    }
+
+    fn open_archive_preview(&mut self, id: u64, archive: PathBuf) {
+        match ArchivePreview::open(id, archive) {
+            Ok(preview) => {
+                self.ui_state.preview = Some(preview);
+                self.ui_state.popup_state = PopupState::ArchivePreview;
             }
+            Err(e) => self.show_error(format!("Failed to preview: {:#}", e)),
+        }
+    }
+
+    /// Extracts the entries picked in the archive preview, then goes back to the Downloads panel.
+    pub(crate) fn extract_preview(&mut self) {
+        let Some(preview) = self.ui_state.preview.as_ref() else {
+            return;
+        };
+        if preview.count_selected() == 0 {
+            self.show_error("Nothing selected, Space picks a file");
+            return;
+        }
+
+        let id = preview.get_challenge_id();
+        let archive = preview.get_archive().to_path_buf();
+        let selection = preview.selection();
+        self.extract_archive(id, archive, Some(selection));
+        self.close_archive_preview();
+    }
+
+    /// A finished download in preview mode waits in the preview, unless another
+    /// popup is open. Only zip archives are listed, others are extracted whole.
+    fn preview_or_extract(&mut self, id: u64, archive: PathBuf) {
+        let is_zip = matches!(ArchiveKind::detect(&archive), Ok(Some(ArchiveKind::Zip)));
+        let popup_free = self.ui_state.preview.is_none()
+            && matches!(
+                self.ui_state.popup_state,
+                PopupState::None | PopupState::Downloads
+            );
+
+        match (is_zip, popup_free) {
+            (false, _) => self.extract_archive(id, archive, None),
+            (true, true) => self.open_archive_preview(id, archive),
+            (true, false) => {}
+        }
+    }
+
+    fn extract_archive(&mut self, id: u64, archive: PathBuf, selection: Option<Selection>) {
+        let options = DownloadOptions::from(self.config.as_ref().unwrap());
+        self.tasks.spawn(TaskKind::Extract(id), async move {
+            TaskResult::Extracted(
+                id,
+                downloader::extract_download(archive, selection, options).await,
+            )
+        });
+    }
+
+    pub(crate) fn close_archive_preview(&mut self) {
+        self.ui_state.preview = None;
+        self.ui_state.popup_state = PopupState::Downloads;
+    }
+
+    pub(crate) fn cancel_selected_download(&mut self) {
+        if let Some(id) = self.ui_state.downloads.cancel_selected() {
+            self.tasks.cancel(TaskKind::Download(id));
+            self.start_queued_downloads();
         }
     }
 
     pub(crate) fn handle_create_vm(&mut self) {
+        if self.tasks.is_running(TaskKind::CreateVm) {
+            return;
+        }
+
         if let Some(selected_item) = self.ui_state.challenges.state.selected() {
-            if self.ui_state.challenges.items[selected_item].create_vm(&self.auth) {
-                // VM created
-                self.vm_state.vm_info =
-                    self.ui_state.challenges.items[selected_item].get_vm_info(&self.auth);
-            }
+            let challenge = self.ui_state.challenges.items[selected_item].clone();
+            let client = self.client.clone();
+            let session = self.session.clone();
+
+            self.tasks.spawn(TaskKind::CreateVm, async move {
+                let result = {
+                    let (challenge, client) = (&challenge, &client);
+                    session
+                        .retry_on_expired(client, |auth| async move {
+                            challenge.create_vm(client, &auth).await?;
+                            challenge.wait_for_vm(client, &auth).await
+                        })
+                        .await
+                };
+                TaskResult::VmCreated(Box::new(challenge), result)
+            });
+        }
+    }
+
+    pub(crate) fn handle_extend_vm(&mut self) {
+        if self.tasks.is_running(TaskKind::ExtendVm) {
+            return;
+        }
+
+        if let Some(challenge) = self.vm_state.challenge.clone() {
+            let client = self.client.clone();
+            let session = self.session.clone();
+
+            self.tasks.spawn(TaskKind::ExtendVm, async move {
+                let (challenge, client) = (&challenge, &client);
+                let result = session
+                    .retry_on_expired(client, |auth| async move {
+                        challenge.extend_vm(client, &auth).await?;
+                        challenge.get_vm_info(client, &auth).await
+                    })
+                    .await;
+                TaskResult::VmExtended(result)
+            });
+        }
+    }
+
+    pub(crate) fn handle_stop_vm(&mut self) {
+        if self.tasks.is_running(TaskKind::StopVm) {
+            return;
+        }
+
+        if let Some(challenge) = self.vm_state.challenge.clone() {
+            let client = self.client.clone();
+            let session = self.session.clone();
+
+            self.tasks.spawn(TaskKind::StopVm, async move {
+                let (challenge, client) = (&challenge, &client);
+                let result = session
+                    .retry_on_expired(client, |auth| async move {
+                        challenge.terminate_vm(client, &auth).await
+                    })
+                    .await;
+                TaskResult::VmStopped(result)
+            });
+        }
+    }
+
+    pub(crate) fn handle_submit_flag(&mut self) {
+        if self.tasks.is_running(TaskKind::SubmitFlag) {
+            return;
+        }
+
+        let flag = self.ui_state.enter_flag.input.trim().to_owned();
+        if flag.is_empty() {
+            return;
+        }
+
+        if let Some(selected_item) = self.ui_state.challenges.state.selected() {
+            let challenge = self.ui_state.challenges.items[selected_item].clone();
+            let client = self.client.clone();
+            let session = self.session.clone();
+
+            self.tasks.spawn(TaskKind::SubmitFlag, async move {
+                let (challenge, client, flag) = (&challenge, &client, &flag);
+                let result = match session
+                    .retry_on_expired(client, |auth| async move {
+                        challenge.submit_flag(client, &auth, flag).await
+                    })
+                    .await
+                {
+                    Err(DreamhackError::AuthExpired) => Ok(FlagResult::NotLoggedIn),
+                    result => result,
+                };
+                TaskResult::Flag(challenge.get_id(), result)
+            });
+        }
+    }
+
+    fn apply_flag_result(&mut self, id: u64, result: FlagResult) {
+        // The list may have changed while the flag was being checked
+        if let Some(challenge) = self
+            .ui_state
+            .challenges
+            .items
+            .iter_mut()
+            .find(|challenge| challenge.get_id() == id)
+        {
+            challenge.apply_flag_result(result);
+        }
+
+        if result == FlagResult::Correct {
+            self.ui_state.enter_flag.reset_cursor();
+        }
+        self.ui_state.flag_result = Some((id, result));
+    }
+}
+
+/// Fetches one page, logged in when the session is.
+async fn fetch_page(
+    client: &DreamhackClient,
+    session: &Session,
+    request: &RequestChallengeList,
+) -> dreamhack::error::Result<(Vec<Challenge>, PageInfo)> {
+    match session.is_logged_in() {
+        true => {
+            session
+                .retry_on_expired(client, |auth| async move {
+                    request.send_request(client, Some(&auth)).await
+                })
+                .await
         }
+        false => request.send_request(client, None).await,
     }
 }
@@ -1,92 +1,1373 @@
 pub mod file_extractor {
     use std::{
+        collections::HashSet,
+        fmt::Display,
         fs::{self, File},
-        io,
-        path::PathBuf,
+        io::{self, Read},
+        path::{Component, Path, PathBuf},
     };
 
+    use anyhow::Context;
+
+    use super::downloader::format_size;
+    use crate::config::ExtractLimits;
+
+    /// Bytes needed to recognise every format, the tar magic is at offset 257
+    const MAGIC_LENGTH: usize = 262;
+
+    /// Suffixes dropped from nested archive names, longest first
+    const ARCHIVE_SUFFIXES: [&str; 12] = [
+        ".tar.gz", ".tar.xz", ".tar.bz2", ".tgz", ".txz", ".tbz2", ".tar", ".zip", ".7z", ".gz",
+        ".xz", ".bz2",
+    ];
+
+    /// Archives expanding to less than this are not held to `max_ratio`
+    const RATIO_FLOOR: u64 = 64 << 20;
+    /// Longest symbolic link target read from an archive
+    const MAX_LINK_LENGTH: u64 = 4096;
+
+    /// Permission bits kept from an archive, setuid and setgid are dropped
+    const MODE_MASK: u32 = 0o1777;
+    const SET_ID_BITS: u32 = 0o6000;
+    /// File type bits of a unix mode, and their value for symbolic links
+    const FILE_TYPE_MASK: u32 = 0o170000;
+    const SYMLINK_TYPE: u32 = 0o120000;
+    /// 7z attribute flag, set when the high 16 bits are a unix mode
+    const UNIX_EXTENSION: u32 = 0x8000;
+
+    /// Archive formats, recognised by their magic bytes rather than the file name.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ArchiveKind {
+        Zip,
+        Tar,
+        Gzip,
+        Xz,
+        Bzip2,
+        SevenZip,
+    }
+
+    /// Entries left out of an extraction, and entries extracted with fewer permissions.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ExtractReport {
+        pub refused: Vec<Refused>,
+        /// `<archive>: <entry>` of the entries whose setuid / setgid bits were dropped
+        pub stripped: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Refused {
+        /// File name of the archive the entry is in
+        pub archive: String,
+        pub entry: String,
+        pub reason: RefuseReason,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum RefuseReason {
+        /// Absolute, or leaves the archive with `..`
+        UnsafePath,
+        /// Symbolic or hard link to a target outside of the archive
+        LinkOutside(String),
+        /// Hard link to an entry that is not a file
+        MissingTarget(String),
+        /// A part of the path is a symbolic link already
+        ThroughLink,
+        /// Devices, fifos, ...
+        Unsupported(String),
+    }
+
+    /// Entry of a zip archive, as listed in the archive preview.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ArchiveEntry {
+        pub name: String,
+        pub size: u64,
+        pub compressed_size: u64,
+        /// Unix mode, `None` for archives made on other systems
+        pub mode: Option<u32>,
+        pub is_dir: bool,
+    }
+
+    /// Entries of the challenge archive to extract, picked in the archive preview.
+    #[derive(Debug, Clone, Default)]
+    pub struct Selection {
+        /// Names as stored in the archive
+        pub entries: HashSet<String>,
+        /// Extract the contents of the single top-level directory into the challenge folder
+        pub flatten: bool,
+    }
+
+    /// What an archive entry is, whatever the format.
+    enum EntryKind {
+        Directory,
+        File,
+        /// Target as stored in the archive
+        Symlink(String),
+        /// Path of an earlier entry of the archive
+        Hardlink(String),
+        Other(String),
+    }
+
+    /// State of one `extract_file`, the limits count nested archives too.
+    struct Extraction<'a> {
+        limits: &'a ExtractLimits,
+        report: ExtractReport,
+        entries: u64,
+        written: u64,
+        /// File name of the archive being extracted
+        archive: String,
+        /// Bytes it may still expand to, from `max_ratio`
+        archive_left: u64,
+        /// Modes of its directories, set once the archive is done
+        dir_modes: Vec<(PathBuf, u32)>,
+        /// Applies to the challenge archive only, taken when it is extracted
+        selection: Option<&'a Selection>,
+    }
+
+    impl ArchiveKind {
+        /// `None` when `path` is not an archive.
+        pub fn detect(path: &Path) -> io::Result<Option<Self>> {
+            let mut header = Vec::with_capacity(MAGIC_LENGTH);
+            File::open(path)?
+                .take(MAGIC_LENGTH as u64)
+                .read_to_end(&mut header)?;
+            Ok(Self::from_magic(&header))
+        }
+
+        fn from_magic(header: &[u8]) -> Option<Self> {
+            match header {
+                [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(ArchiveKind::Zip),
+                [0x1f, 0x8b, ..] => Some(ArchiveKind::Gzip),
+                [0xfd, b'7', b'z', b'X', b'Z', 0, ..] => Some(ArchiveKind::Xz),
+                [b'B', b'Z', b'h', ..] => Some(ArchiveKind::Bzip2),
+                [b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c, ..] => Some(ArchiveKind::SevenZip),
+                _ if header.get(257..262) == Some(b"ustar") => Some(ArchiveKind::Tar),
+                _ => None,
+            }
+        }
+    }
+
+    impl ExtractReport {
+        pub fn is_empty(&self) -> bool {
+            self.refused.is_empty() && self.stripped.is_empty()
+        }
+
+        /// One line per refused entry, then the stripped ones.
+        pub fn lines(&self) -> Vec<String> {
+            self.refused
+                .iter()
+                .map(Refused::to_string)
+                .chain(
+                    self.stripped
+                        .iter()
+                        .map(|entry| format!("{} (setuid/setgid removed)", entry)),
+                )
+                .collect()
+        }
+    }
+
+    impl Display for ExtractReport {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match (self.refused.len(), self.stripped.len()) {
+                (0, 0) => write!(f, "nothing refused"),
+                (refused, 0) => write!(f, "{} entries refused", refused),
+                (0, stripped) => write!(f, "{} setuid/setgid removed", stripped),
+                (refused, stripped) => write!(
+                    f,
+                    "{} entries refused, {} setuid/setgid removed",
+                    refused, stripped
+                ),
+            }
+        }
+    }
+
+    impl Selection {
+        /// Name to extract `name` as, `None` when it is not selected.
+        fn target_name(&self, name: &str) -> Option<String> {
+            if !self.entries.contains(name) {
+                return None;
+            }
+
+            let top = self
+                .flatten
+                .then(|| top_directory(self.entries.iter().map(String::as_str)))
+                .flatten();
+            match top {
+                // The directory itself is dropped
+                Some(top) => name
+                    .strip_prefix(&format!("{}/", top))
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string),
+                None => Some(name.to_string()),
+            }
+        }
+    }
+
+    impl Display for Refused {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}: {} ({})", self.archive, self.entry, self.reason)
+        }
+    }
+
+    impl Display for RefuseReason {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                RefuseReason::UnsafePath => write!(f, "path leaves the archive"),
+                RefuseReason::LinkOutside(target) => {
+                    write!(f, "link to {} outside of the archive", target)
+                }
+                RefuseReason::MissingTarget(target) => {
+                    write!(f, "hard link to {}, which is not a file", target)
+                }
+                RefuseReason::ThroughLink => write!(f, "would write through a symbolic link"),
+                RefuseReason::Unsupported(kind) => write!(f, "{} entries are not extracted", kind),
+            }
+        }
+    }
+
+    /// Lists the entries of the zip archive at `path`, in archive order.
+    pub fn list_zip(path: &Path) -> Result<Vec<ArchiveEntry>, anyhow::Error> {
+        if ArchiveKind::detect(path)? != Some(ArchiveKind::Zip) {
+            anyhow::bail!(
+                "{} is not a zip archive, only zip archives can be previewed",
+                path.display()
+            );
+        }
+
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut entries = Vec::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            entries.push(ArchiveEntry {
+                name: file.name().to_string(),
+                size: file.size(),
+                compressed_size: file.compressed_size(),
+                mode: file.unix_mode(),
+                is_dir: file.is_dir(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The directory every name is in, `None` when some entry is at the top level.
+    pub fn top_directory<'a>(names: impl IntoIterator<Item = &'a str>) -> Option<String> {
+        let mut top = None;
+        for name in names {
+            let (first, _) = name.split_once('/')?;
+            match top {
+                None => top = Some(first),
+                Some(top) if top == first => {}
+                Some(_) => return None,
+            }
+        }
+        top.map(str::to_string)
+    }
+
+    /// Extracts the archive at `path` into `workdir/<repository>`.
+    ///
+    /// Only the entries of `selection` are extracted from a zip archive, when given.
+    /// Archives found inside are extracted next to themselves (`deploy.tar.gz` into
+    /// `deploy/`), `nested_depth` levels deep. Entries that are unsafe to write are
+    /// left out and listed in the report; going past `limits` stops the extraction.
     pub fn extract_file(
         path: PathBuf,
         workdir: PathBuf,
         repository: &str,
-    ) -> Result<(), anyhow::Error> {
+        selection: Option<&Selection>,
+        nested_depth: u32,
+        limits: &ExtractLimits,
+    ) -> Result<ExtractReport, anyhow::Error> {
         #[cfg(debug_assertions)]
-        log::info!("Extracting file from path: {}", path.to_str().unwrap());
-
-        match path.extension().unwrap().to_str() {
-            Some("zip") => Ok(extract_zip(workdir, repository)?),
-            Some(ext) => Err(anyhow::anyhow!(
-                "The file extension is not supported. Supported extensions: zip\n
-                    Current extension: {}",
-                ext
-            )),
-            None => Err(anyhow::anyhow!(
-                "The file extension is not supported. Supported extensions: zip"
-            )),
-        }
+        log::info!("Extracting file from path: {}", path.display());
+
+        let kind = ArchiveKind::detect(&path)?.ok_or(anyhow::anyhow!(
+            "{} is not an archive. Supported formats: zip, tar, gzip, xz, bzip2, 7z",
+            path.display()
+        ))?;
+
+        let mut extraction = Extraction {
+            limits,
+            report: ExtractReport::default(),
+            entries: 0,
+            written: 0,
+            archive: String::new(),
+            archive_left: 0,
+            dir_modes: Vec::new(),
+            selection,
+        };
+        let target = extraction.extract_as(&path, kind, &workdir, repository)?;
+        extraction.extract_nested(&target, nested_depth)?;
+        Ok(extraction.report)
     }
 
-    fn extract_zip(path: PathBuf, repository: &str) -> Result<(), anyhow::Error> {
-        // Construct the path to the zip file
-        let zip_path = path.clone().join(format!("{}.zip", repository));
-        #[cfg(debug_assertions)]
-        log::info!("Extracting file from path: {}", zip_path.to_str().unwrap());
+    impl Extraction<'_> {
+        /// Extracts `archive` as `parent/name`, a directory unless it is a single compressed file.
+        fn extract_as(
+            &mut self,
+            archive: &Path,
+            kind: ArchiveKind,
+            parent: &Path,
+            name: &str,
+        ) -> Result<PathBuf, anyhow::Error> {
+            let target = parent.join(name);
+            self.archive = archive
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            self.archive_left = fs::metadata(archive)?
+                .len()
+                .saturating_mul(self.limits.max_ratio)
+                .max(RATIO_FLOOR);
 
-        // Open the zip file
-        let f = File::open(zip_path)?;
-        let mut archive = zip::ZipArchive::new(f)?;
+            if is_symlink(&target) {
+                self.refuse(name, RefuseReason::ThroughLink);
+                return Ok(target);
+            }
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-
-            // Get the path to extract the file to.
-            let outpath = match file.enclosed_name() {
-                Some(path_inside_zip) => {
-                    // Construct the extraction path by placing the repository directory under the main path.
-                    let mut new_outpath = path.clone();
-                    new_outpath.push(repository);
-                    new_outpath.push(path_inside_zip);
-                    new_outpath
+            #[cfg(debug_assertions)]
+            log::info!(
+                "Extracting {:?} {} to {}",
+                kind,
+                archive.display(),
+                target.display()
+            );
+
+            let file = File::open(archive)?;
+            let selection = self.selection.take();
+            match kind {
+                ArchiveKind::Zip => self.extract_zip(file, &target, selection)?,
+                ArchiveKind::Tar => self.extract_tar(file, &target)?,
+                ArchiveKind::Gzip => {
+                    self.extract_stream(flate2::read::GzDecoder::new(file), &target)?
                 }
-                None => continue, // Skip to the next file if the path is None.
+                ArchiveKind::Xz => self.extract_stream(xz2::read::XzDecoder::new(file), &target)?,
+                ArchiveKind::Bzip2 => {
+                    self.extract_stream(bzip2::read::BzDecoder::new(file), &target)?
+                }
+                ArchiveKind::SevenZip => self.extract_7z(archive, &target)?,
+            }
+
+            // Read-only directories could not have been filled before
+            for (path, mode) in self.dir_modes.drain(..).rev() {
+                set_permissions(&path, mode)?;
+            }
+            Ok(target)
+        }
+
+        /// Extracts the archives under `path`, then the archives inside those, `depth` levels deep.
+        fn extract_nested(&mut self, path: &Path, depth: u32) -> Result<(), anyhow::Error> {
+            if depth == 0 {
+                return Ok(());
+            }
+
+            for file in list_files(path)? {
+                let Some(kind) = ArchiveKind::detect(&file)? else {
+                    continue;
+                };
+                let (Some(parent), Some(file_name)) = (file.parent(), file.file_name()) else {
+                    continue;
+                };
+
+                let file_name = file_name.to_string_lossy();
+                let name = match ARCHIVE_SUFFIXES
+                    .iter()
+                    .find_map(|suffix| file_name.strip_suffix(suffix))
+                {
+                    Some(stem) if !stem.is_empty() => stem.to_string(),
+                    // e.g. a docker image saved without an extension
+                    _ => format!("{}_extracted", file_name),
+                };
+
+                let target = self
+                    .extract_as(&file, kind, parent, &name)
+                    .with_context(|| format!("Failed to extract {}", file.display()))?;
+                self.extract_nested(&target, depth - 1)?;
+            }
+            Ok(())
+        }
+
+        fn extract_zip(
+            &mut self,
+            file: File,
+            dest: &Path,
+            selection: Option<&Selection>,
+        ) -> Result<(), anyhow::Error> {
+            let mut archive = zip::ZipArchive::new(file)?;
+            fs::create_dir_all(dest)?;
+
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i)?;
+                let name = match selection {
+                    Some(selection) => match selection.target_name(file.name()) {
+                        Some(name) => name,
+                        None => continue,
+                    },
+                    None => file.name().to_string(),
+                };
+                let mode = file.unix_mode();
+                let kind = if file.is_dir() {
+                    EntryKind::Directory
+                } else if file.is_symlink() {
+                    EntryKind::Symlink(read_link_target(&mut file)?)
+                } else {
+                    EntryKind::File
+                };
+
+                self.add_entry(dest, &name, kind, &mut file, mode)?;
+            }
+            Ok(())
+        }
+
+        fn extract_tar(&mut self, reader: impl Read, dest: &Path) -> Result<(), anyhow::Error> {
+            let mut archive = tar::Archive::new(reader);
+            fs::create_dir_all(dest)?;
+
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let name = entry.path()?.to_string_lossy().into_owned();
+                let link_name = entry
+                    .link_name()?
+                    .map(|link| link.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let header = entry.header();
+                let mode = header.mode().ok();
+                let entry_type = header.entry_type();
+
+                let kind = if entry_type.is_pax_global_extensions() {
+                    continue;
+                } else if entry_type.is_dir() {
+                    EntryKind::Directory
+                } else if entry_type.is_file() || entry_type.is_contiguous() {
+                    EntryKind::File
+                } else if entry_type.is_symlink() {
+                    EntryKind::Symlink(link_name)
+                } else if entry_type.is_hard_link() {
+                    EntryKind::Hardlink(link_name)
+                } else if entry_type.is_character_special() {
+                    EntryKind::Other("character device".to_string())
+                } else if entry_type.is_block_special() {
+                    EntryKind::Other("block device".to_string())
+                } else if entry_type.is_fifo() {
+                    EntryKind::Other("fifo".to_string())
+                } else {
+                    EntryKind::Other(format!("{:?}", entry_type))
+                };
+
+                self.add_entry(dest, &name, kind, &mut entry, mode)?;
+            }
+            Ok(())
+        }
+
+        /// A compressed tarball is unpacked into the directory `target`, any other
+        /// compressed file is written to `target` as is.
+        fn extract_stream(
+            &mut self,
+            reader: impl Read,
+            target: &Path,
+        ) -> Result<(), anyhow::Error> {
+            let mut reader = reader;
+            let mut header = Vec::with_capacity(MAGIC_LENGTH);
+            (&mut reader)
+                .take(MAGIC_LENGTH as u64)
+                .read_to_end(&mut header)?;
+            let is_tar = ArchiveKind::from_magic(&header) == Some(ArchiveKind::Tar);
+            let mut reader = io::Cursor::new(header).chain(reader);
+
+            if is_tar {
+                return self.extract_tar(reader, target);
+            }
+            let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+                anyhow::bail!("Can not extract to {}", target.display());
             };
+            self.add_entry(
+                parent,
+                &name.to_string_lossy(),
+                EntryKind::File,
+                &mut reader,
+                None,
+            )
+        }
+
+        fn extract_7z(&mut self, archive: &Path, dest: &Path) -> Result<(), anyhow::Error> {
+            let mut reader =
+                sevenz_rust::SevenZReader::open(archive, sevenz_rust::Password::empty())?;
+            fs::create_dir_all(dest)?;
+
+            let mut result = Ok(());
+            reader.for_each_entries(|entry, data| {
+                if entry.is_anti_item() {
+                    return Ok(true);
+                }
+                let attributes = entry.windows_attributes();
+                let mode = (entry.has_windows_attributes && attributes & UNIX_EXTENSION != 0)
+                    .then_some(attributes >> 16);
+                let kind = if entry.is_directory() {
+                    EntryKind::Directory
+                } else if mode.is_some_and(|mode| mode & FILE_TYPE_MASK == SYMLINK_TYPE) {
+                    EntryKind::Symlink(read_link_target(data).map_err(sevenz_rust::Error::io)?)
+                } else {
+                    EntryKind::File
+                };
+
+                // Errors of `add_entry` are not 7z errors, they stop the loop and are returned below
+                result = self.add_entry(dest, entry.name(), kind, data, mode);
+                Ok(result.is_ok())
+            })?;
+            result
+        }
+
+        /// Writes one entry below `root`, or adds it to the report when it is unsafe.
+        fn add_entry(
+            &mut self,
+            root: &Path,
+            name: &str,
+            kind: EntryKind,
+            data: &mut dyn Read,
+            mode: Option<u32>,
+        ) -> Result<(), anyhow::Error> {
+            self.entries += 1;
+            if self.entries > self.limits.max_entries {
+                anyhow::bail!(
+                    "Stopped extracting {}, the download has more than {} entries (extract_limits.max_entries)",
+                    self.archive,
+                    self.limits.max_entries
+                );
+            }
+
+            let Some(outpath) = enclosed_path(root, name) else {
+                self.refuse(name, RefuseReason::UnsafePath);
+                return Ok(());
+            };
+            // A symbolic link entry replaces an earlier link, files are never written through one
+            let checked = match kind {
+                EntryKind::Symlink(_) => outpath.parent().unwrap_or(root),
+                _ => &outpath,
+            };
+            if through_link(root, checked) {
+                self.refuse(name, RefuseReason::ThroughLink);
+                return Ok(());
+            }
 
             #[cfg(debug_assertions)]
-            log::info!(
-                "Extracting file {} ({} bytes) to {}",
-                file.name(),
-                file.size(),
-                outpath.display()
-            );
+            log::info!("Extracting {} to {}", name, outpath.display());
 
-            // Check if the file is a directory.
-            if file.name().ends_with('/') {
-                #[cfg(debug_assertions)]
-                log::info!("Creating directory: {}", outpath.display());
-                fs::create_dir_all(&outpath)?; // Create the directory.
-            } else {
-                // Create parent directories if they don't exist.
-                if let Some(p) = outpath.parent() {
-                    if !p.exists() {
-                        fs::create_dir_all(p)?;
+            match kind {
+                EntryKind::Directory => fs::create_dir_all(&outpath)?,
+                EntryKind::File => self.write_file(&outpath, data)?,
+                EntryKind::Symlink(target) => {
+                    if !link_inside(root, &outpath, &target) {
+                        self.refuse(name, RefuseReason::LinkOutside(target));
+                        return Ok(());
                     }
+                    return self.create_symlink(name, &target, &outpath);
                 }
+                // Extracted as a copy, so it can not be used to change the original
+                EntryKind::Hardlink(target) => {
+                    let Some(source) =
+                        enclosed_path(root, &target).filter(|source| !through_link(root, source))
+                    else {
+                        self.refuse(name, RefuseReason::LinkOutside(target));
+                        return Ok(());
+                    };
+                    if !fs::symlink_metadata(&source).is_ok_and(|metadata| metadata.is_file()) {
+                        self.refuse(name, RefuseReason::MissingTarget(target));
+                        return Ok(());
+                    }
+                    self.write_file(&outpath, &mut File::open(source)?)?;
+                }
+                EntryKind::Other(kind) => {
+                    self.refuse(name, RefuseReason::Unsupported(kind));
+                    return Ok(());
+                }
+            }
+
+            if let Some(mode) = mode {
+                if mode & SET_ID_BITS != 0 {
+                    self.report
+                        .stripped
+                        .push(format!("{}: {}", self.archive, name));
+                }
+                let mode = mode & MODE_MASK;
+                match outpath.is_dir() {
+                    true => self.dir_modes.push((outpath, mode)),
+                    false => set_permissions(&outpath, mode)?,
+                }
+            }
+            Ok(())
+        }
+
+        /// Copies `data` to `path`, within what is left of the size limits.
+        fn write_file(&mut self, path: &Path, data: &mut dyn Read) -> Result<(), anyhow::Error> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let total_left = self.limits.max_total_size.saturating_sub(self.written);
+            let allowed = total_left.min(self.archive_left);
+            let mut file = File::create(path)?;
+            let copied = io::copy(&mut data.take(allowed.saturating_add(1)), &mut file)?;
+
+            if copied > allowed {
+                drop(file);
+                let _ = fs::remove_file(path);
+                match allowed == total_left {
+                    true => anyhow::bail!(
+                        "Stopped extracting {}, the download is larger than {} (extract_limits.max_total_size)",
+                        self.archive,
+                        format_size(self.limits.max_total_size)
+                    ),
+                    false => anyhow::bail!(
+                        "Stopped extracting {}, it expands to more than {} times its size (extract_limits.max_ratio), likely a zip bomb",
+                        self.archive,
+                        self.limits.max_ratio
+                    ),
+                }
+            }
+
+            self.written += copied;
+            self.archive_left -= copied;
+            Ok(())
+        }
+
+        #[cfg(unix)]
+        fn create_symlink(
+            &mut self,
+            _name: &str,
+            target: &str,
+            path: &Path,
+        ) -> Result<(), anyhow::Error> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if fs::symlink_metadata(path).is_ok_and(|metadata| !metadata.is_dir()) {
+                fs::remove_file(path)?;
+            }
+            std::os::unix::fs::symlink(target, path)?;
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        fn create_symlink(
+            &mut self,
+            name: &str,
+            _target: &str,
+            _path: &Path,
+        ) -> Result<(), anyhow::Error> {
+            self.refuse(name, RefuseReason::Unsupported("symbolic link".to_string()));
+            Ok(())
+        }
+
+        fn refuse(&mut self, entry: &str, reason: RefuseReason) {
+            #[cfg(debug_assertions)]
+            log::warn!("Refused {} of {}: {}", entry, self.archive, reason);
 
-                // Create and copy the file contents to the output path.
-                let mut outfile = File::create(&outpath)?;
-                io::copy(&mut file, &mut outfile)?;
+            self.report.refused.push(Refused {
+                archive: self.archive.clone(),
+                entry: entry.to_string(),
+                reason,
+            });
+        }
+    }
+
+    /// Regular files under `path` (or `path` itself), symlinks are not followed.
+    fn list_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+        let file_type = fs::symlink_metadata(path)?.file_type();
+        if file_type.is_file() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        if !file_type.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            files.extend(list_files(&entry?.path())?);
+        }
+        Ok(files)
+    }
+
+    /// `dest/name`, `None` for absolute names and names that leave `dest`.
+    fn enclosed_path(dest: &Path, name: &str) -> Option<PathBuf> {
+        let mut path = dest.to_path_buf();
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(part) => path.push(part),
+                Component::CurDir => {}
+                _ => return None,
             }
+        }
+        Some(path)
+    }
+
+    fn is_symlink(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
 
-            // Set file permissions if running on a Unix-like system.
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
+    /// Whether a part of `path` below `root` is a symbolic link already.
+    fn through_link(root: &Path, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return true;
+        };
+        let mut current = root.to_path_buf();
+        relative.components().any(|component| {
+            current.push(component);
+            is_symlink(&current)
+        })
+    }
+
+    /// Whether the symbolic link at `link` points inside `root`.
+    ///
+    /// `..` is only allowed at the start of `target`, after a name it could climb
+    /// out through another link.
+    fn link_inside(root: &Path, link: &Path, target: &str) -> bool {
+        let Some(mut dir) = link.parent().map(Path::to_path_buf) else {
+            return false;
+        };
 
-                if let Some(mode) = file.unix_mode() {
-                    fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
+        let mut descended = false;
+        for component in Path::new(target).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir if !descended && dir != root => {
+                    dir.pop();
                 }
+                Component::Normal(_) => descended = true,
+                _ => return false,
             }
         }
+        !target.is_empty()
+    }
+
+    fn read_link_target(data: &mut dyn Read) -> io::Result<String> {
+        let mut target = Vec::new();
+        data.take(MAX_LINK_LENGTH).read_to_end(&mut target)?;
+        Ok(String::from_utf8_lossy(&target).into_owned())
+    }
+
+    fn set_permissions(path: &Path, mode: u32) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+        #[cfg(not(unix))]
+        let _ = (path, mode);
         Ok(())
     }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{Cursor, Write};
+
+        use zip::write::SimpleFileOptions;
+
+        use super::*;
+
+        /// Tar entries as `(name, kind, data or link target, mode)`.
+        type TarEntry<'a> = (&'a str, tar::EntryType, &'a [u8], u32);
+
+        fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+            let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, data) in entries {
+                zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(data).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        }
+
+        /// Names are written as is, `Header::set_path` refuses the unsafe ones.
+        fn tar_archive(entries: &[TarEntry]) -> Vec<u8> {
+            let mut builder = tar::Builder::new(Vec::new());
+            for &(name, kind, data, mode) in entries {
+                let mut header = tar::Header::new_gnu();
+                header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+                header.set_entry_type(kind);
+                header.set_mode(mode);
+                let data = match kind.is_symlink() || kind.is_hard_link() {
+                    true => {
+                        header.as_old_mut().linkname[..data.len()].copy_from_slice(data);
+                        &[][..]
+                    }
+                    false => data,
+                };
+                header.set_size(data.len() as u64);
+                header.set_cksum();
+                builder.append(&header, data).unwrap();
+            }
+            builder.into_inner().unwrap()
+        }
+
+        /// Extracts `archive` into `<tempdir>/work/chal`.
+        fn extract(
+            dir: &Path,
+            file_name: &str,
+            archive: &[u8],
+            limits: &ExtractLimits,
+        ) -> Result<ExtractReport, anyhow::Error> {
+            let path = dir.join(file_name);
+            fs::write(&path, archive).unwrap();
+            extract_file(path, dir.join("work"), "chal", None, 0, limits)
+        }
+
+        fn refused(report: &ExtractReport) -> Vec<(&str, &RefuseReason)> {
+            report
+                .refused
+                .iter()
+                .map(|refused| (refused.entry.as_str(), &refused.reason))
+                .collect()
+        }
+
+        #[test]
+        fn detects_archives_by_magic() {
+            let zip = zip_archive(&[("a", b"a")]);
+            let tar = tar_archive(&[("a", tar::EntryType::Regular, b"a", 0o644)]);
+
+            assert_eq!(ArchiveKind::from_magic(&zip), Some(ArchiveKind::Zip));
+            assert_eq!(ArchiveKind::from_magic(&tar), Some(ArchiveKind::Tar));
+            assert_eq!(
+                ArchiveKind::from_magic(&[0x1f, 0x8b, 8]),
+                Some(ArchiveKind::Gzip)
+            );
+            assert_eq!(ArchiveKind::from_magic(b"plain text"), None);
+        }
+
+        #[test]
+        fn encloses_paths() {
+            let root = Path::new("/work");
+
+            assert_eq!(
+                enclosed_path(root, "a/./b"),
+                Some(PathBuf::from("/work/a/b"))
+            );
+            assert_eq!(enclosed_path(root, "../a"), None);
+            assert_eq!(enclosed_path(root, "a/../../b"), None);
+            assert_eq!(enclosed_path(root, "/etc/passwd"), None);
+        }
+
+        #[test]
+        fn checks_link_targets() {
+            let root = Path::new("/work");
+            let link = Path::new("/work/dir/link");
+
+            assert!(link_inside(root, link, "file"));
+            assert!(link_inside(root, link, "../file"));
+            assert!(!link_inside(root, link, "../../file"));
+            // Could climb out through another link named `sub`
+            assert!(!link_inside(root, link, "sub/../../file"));
+            assert!(!link_inside(root, link, "/etc/passwd"));
+            assert!(!link_inside(root, link, ""));
+        }
+
+        #[test]
+        fn refuses_unsafe_paths() {
+            let dir = tempfile::tempdir().unwrap();
+            let archive = zip_archive(&[
+                ("../evil.txt", b"evil"),
+                ("/tmp/absolute.txt", b"evil"),
+                ("a/../../../evil.txt", b"evil"),
+                ("ok/file.txt", b"ok"),
+            ]);
+
+            let report =
+                extract(dir.path(), "chal.zip", &archive, &ExtractLimits::default()).unwrap();
+
+            assert_eq!(
+                refused(&report),
+                vec![
+                    ("../evil.txt", &RefuseReason::UnsafePath),
+                    ("/tmp/absolute.txt", &RefuseReason::UnsafePath),
+                    ("a/../../../evil.txt", &RefuseReason::UnsafePath),
+                ]
+            );
+            assert_eq!(report.refused[0].archive, "chal.zip");
+            assert!(!dir.path().join("work/evil.txt").exists());
+            assert!(!dir.path().join("evil.txt").exists());
+            assert_eq!(
+                fs::read(dir.path().join("work/chal/ok/file.txt")).unwrap(),
+                b"ok"
+            );
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn refuses_links_outside() {
+            use tar::EntryType::{Link, Regular, Symlink};
+
+            let dir = tempfile::tempdir().unwrap();
+            let archive = tar_archive(&[
+                ("up", Symlink, b"../../outside", 0o777),
+                ("abs", Symlink, b"/etc", 0o777),
+                ("hard", Link, b"../outside", 0o644),
+                ("file.txt", Regular, b"data", 0o644),
+                ("inside", Symlink, b"file.txt", 0o777),
+            ]);
+
+            let report =
+                extract(dir.path(), "chal.tar", &archive, &ExtractLimits::default()).unwrap();
+
+            let outside = RefuseReason::LinkOutside("../../outside".to_string());
+            let etc = RefuseReason::LinkOutside("/etc".to_string());
+            let hard = RefuseReason::LinkOutside("../outside".to_string());
+            assert_eq!(
+                refused(&report),
+                vec![("up", &outside), ("abs", &etc), ("hard", &hard)]
+            );
+            let chal = dir.path().join("work/chal");
+            assert!(!is_symlink(&chal.join("up")));
+            assert!(!is_symlink(&chal.join("abs")));
+            assert_eq!(fs::read(chal.join("inside")).unwrap(), b"data");
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn refuses_writes_through_links() {
+            use tar::EntryType::{Directory, Regular, Symlink};
+
+            let dir = tempfile::tempdir().unwrap();
+            let archive = tar_archive(&[
+                ("sub", Directory, b"", 0o755),
+                ("link", Symlink, b"sub", 0o777),
+                ("link/file.txt", Regular, b"data", 0o644),
+            ]);
+
+            let report =
+                extract(dir.path(), "chal.tar", &archive, &ExtractLimits::default()).unwrap();
+
+            assert_eq!(
+                refused(&report),
+                vec![("link/file.txt", &RefuseReason::ThroughLink)]
+            );
+            assert!(!dir.path().join("work/chal/sub/file.txt").exists());
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn refuses_an_existing_link_as_target() {
+            let dir = tempfile::tempdir().unwrap();
+            let outside = dir.path().join("outside");
+            fs::create_dir_all(&outside).unwrap();
+            fs::create_dir_all(dir.path().join("work")).unwrap();
+            std::os::unix::fs::symlink(&outside, dir.path().join("work/chal")).unwrap();
+
+            let archive = zip_archive(&[("file.txt", b"data")]);
+            let report =
+                extract(dir.path(), "chal.zip", &archive, &ExtractLimits::default()).unwrap();
+
+            assert_eq!(refused(&report), vec![("chal", &RefuseReason::ThroughLink)]);
+            assert!(!outside.join("file.txt").exists());
+        }
+
+        #[test]
+        fn stops_at_max_entries() {
+            let dir = tempfile::tempdir().unwrap();
+            let archive = zip_archive(&[("a", b"a"), ("b", b"b"), ("c", b"c")]);
+            let limits = ExtractLimits {
+                max_entries: 2,
+                ..Default::default()
+            };
+
+            let error = extract(dir.path(), "chal.zip", &archive, &limits).unwrap_err();
+
+            assert!(
+                error.to_string().contains("more than 2 entries"),
+                "{}",
+                error
+            );
+            assert!(error.to_string().contains("max_entries"), "{}", error);
+            assert!(!dir.path().join("work/chal/c").exists());
+        }
+
+        #[test]
+        fn stops_at_max_total_size() {
+            let dir = tempfile::tempdir().unwrap();
+            let archive = tar_archive(&[
+                ("small", tar::EntryType::Regular, &[0; 8], 0o644),
+                ("large", tar::EntryType::Regular, &[0; 16], 0o644),
+            ]);
+            let limits = ExtractLimits {
+                max_total_size: 20,
+                ..Default::default()
+            };
+
+            let error = extract(dir.path(), "chal.tar", &archive, &limits).unwrap_err();
+
+            assert!(error.to_string().contains("max_total_size"), "{}", error);
+            // The partial file is removed
+            assert!(dir.path().join("work/chal/small").exists());
+            assert!(!dir.path().join("work/chal/large").exists());
+        }
+
+        #[test]
+        fn stops_at_max_ratio() {
+            let dir = tempfile::tempdir().unwrap();
+            let zeros = vec![0; (RATIO_FLOOR + 1) as usize];
+            let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            zip.start_file("bomb", SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(&zeros).unwrap();
+            let archive = zip.finish().unwrap().into_inner();
+            let limits = ExtractLimits {
+                max_ratio: 2,
+                ..Default::default()
+            };
+
+            let error = extract(dir.path(), "chal.zip", &archive, &limits).unwrap_err();
+
+            assert!(error.to_string().contains("max_ratio"), "{}", error);
+            assert!(!dir.path().join("work/chal/bomb").exists());
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn strips_setuid_and_setgid() {
+            use std::os::unix::fs::PermissionsExt;
+            use tar::EntryType::{Directory, Regular};
+
+            let dir = tempfile::tempdir().unwrap();
+            let archive = tar_archive(&[
+                ("bin", Directory, b"", 0o2755),
+                ("bin/suid", Regular, b"x", 0o4755),
+                ("bin/sticky", Regular, b"x", 0o1644),
+                ("bin/plain", Regular, b"x", 0o640),
+            ]);
+
+            let report =
+                extract(dir.path(), "chal.tar", &archive, &ExtractLimits::default()).unwrap();
+
+            assert_eq!(
+                report.stripped,
+                vec![
+                    "chal.tar: bin".to_string(),
+                    "chal.tar: bin/suid".to_string()
+                ]
+            );
+            let mode = |name: &str| {
+                let path = dir.path().join("work/chal").join(name);
+                fs::metadata(path).unwrap().permissions().mode() & 0o7777
+            };
+            assert_eq!(mode("bin"), 0o755);
+            assert_eq!(mode("bin/suid"), 0o755);
+            assert_eq!(mode("bin/sticky"), 0o1644);
+            assert_eq!(mode("bin/plain"), 0o640);
+        }
+
+        #[test]
+        fn reports_refused_and_stripped_entries() {
+            let report = ExtractReport {
+                refused: vec![Refused {
+                    archive: "chal.zip".to_string(),
+                    entry: "../x".to_string(),
+                    reason: RefuseReason::UnsafePath,
+                }],
+                stripped: vec!["chal.zip: run".to_string()],
+            };
+
+            assert_eq!(
+                report.to_string(),
+                "1 entries refused, 1 setuid/setgid removed"
+            );
+            assert_eq!(
+                report.lines(),
+                vec![
+                    "chal.zip: ../x (path leaves the archive)",
+                    "chal.zip: run (setuid/setgid removed)",
+                ]
+            );
+            assert_eq!(ExtractReport::default().to_string(), "nothing refused");
+        }
+    }
+}
+
+pub mod downloader {
+    use std::{
+        fmt::Display,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use anyhow::Context;
+    use reqwest::StatusCode;
+    use tokio::io::AsyncWriteExt;
+
+    use crate::{
+        config::{Config, DownloadPolicy, ExtractLimits},
+        dreamhack::{challenge::handle::Challenge, client::DreamhackClient, error::DreamhackError},
+    };
+
+    use super::file_extractor::{ExtractReport, Selection};
+
+    /// Bytes received by a running download, shared with whoever shows it.
+    #[derive(Debug, Default)]
+    pub struct Progress {
+        received: AtomicU64,
+        /// 0 while the size is unknown
+        total: AtomicU64,
+    }
+
+    /// What happens around the download, from the config.
+    #[derive(Debug, Clone, Copy)]
+    pub struct DownloadOptions {
+        pub extract: bool,
+        pub keep: bool,
+        pub policy: DownloadPolicy,
+        pub nested_depth: u32,
+        pub limits: ExtractLimits,
+    }
+
+    pub enum Downloaded {
+        /// Path of the archive, already removed again unless it is kept, and what
+        /// extracting it left out (`None` when it was not extracted)
+        Saved(PathBuf, Option<ExtractReport>),
+        /// Path of the earlier download, left alone by `DownloadPolicy::Skip`
+        Skipped(PathBuf),
+    }
+
+    impl From<&Config> for DownloadOptions {
+        fn from(config: &Config) -> Self {
+            DownloadOptions {
+                extract: config.extract_chall_file,
+                keep: config.keep_chall_file,
+                policy: config.download_policy,
+                nested_depth: config.extract_nested_depth,
+                limits: config.extract_limits,
+            }
+        }
+    }
+
+    impl Progress {
+        pub fn get_received(&self) -> u64 {
+            self.received.load(Ordering::Relaxed)
+        }
+
+        pub fn get_total(&self) -> Option<u64> {
+            Some(self.total.load(Ordering::Relaxed)).filter(|total| *total > 0)
+        }
+
+        /// Between 0 and 1, `None` while the size is unknown.
+        pub fn get_ratio(&self) -> Option<f64> {
+            self.get_total()
+                .map(|total| (self.get_received() as f64 / total as f64).min(1.0))
+        }
+
+        fn set(&self, received: u64, total: Option<u64>) {
+            self.received.store(received, Ordering::Relaxed);
+            self.total.store(total.unwrap_or(0), Ordering::Relaxed);
+        }
+
+        fn add(&self, received: u64) {
+            self.received.fetch_add(received, Ordering::Relaxed);
+        }
+    }
+
+    impl Display for Progress {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match (self.get_total(), self.get_ratio()) {
+                (Some(total), Some(ratio)) => write!(
+                    f,
+                    "{} / {} ({:.0}%)",
+                    format_size(self.get_received()),
+                    format_size(total),
+                    ratio * 100.0
+                ),
+                _ => write!(f, "{}", format_size(self.get_received())),
+            }
+        }
+    }
+
+    /// `1.2 MiB`, `640 B`, ...
+    pub fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if bytes < 1024 {
+            return format!("{} B", bytes);
+        }
+        let mut size = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+
+    /// Downloads the challenge files into `workdir` and extracts them.
+    ///
+    /// The archive is streamed into `<repository>.zip.part` and only renamed into
+    /// place once complete, a cancelled or failed download resumes from there.
+    pub async fn download_file(
+        client: &DreamhackClient,
+        challenge: &Challenge,
+        workdir: PathBuf,
+        options: DownloadOptions,
+        progress: &Progress,
+    ) -> anyhow::Result<Downloaded> {
+        let repository = challenge.get_metadata().get_repository().to_owned();
+        let name = match choose_name(&workdir, &repository, options.extract, options.policy) {
+            Ok(name) => name,
+            Err(existing) => return Ok(Downloaded::Skipped(existing)),
+        };
+        let file_path = workdir.join(format!("{}.zip", name));
+        let part_path = workdir.join(format!("{}.zip.part", repository));
+
+        fetch_to_file(client, challenge, &part_path, progress).await?;
+        tokio::fs::rename(&part_path, &file_path)
+            .await
+            .context("Failed to move the download into place")?;
+
+        let mut report = None;
+        if options.extract {
+            let (path, workdir, name) = (file_path.clone(), workdir.clone(), name.clone());
+            let extracted = tokio::task::spawn_blocking(move || {
+                super::file_extractor::extract_file(
+                    path,
+                    workdir,
+                    &name,
+                    None,
+                    options.nested_depth,
+                    &options.limits,
+                )
+            })
+            .await??;
+            report = Some(extracted);
+        }
+
+        if !options.keep {
+            tokio::fs::remove_file(&file_path)
+                .await
+                .context("Failed to remove file")?;
+        }
+        Ok(Downloaded::Saved(file_path, report))
+    }
+
+    /// Extracts an archive downloaded without extracting it, only the entries of
+    /// `selection` when given.
+    ///
+    /// The archive is extracted next to itself, `chal.zip` into `chal/`, and removed
+    /// afterwards unless it is kept.
+    pub async fn extract_download(
+        archive: PathBuf,
+        selection: Option<Selection>,
+        options: DownloadOptions,
+    ) -> anyhow::Result<ExtractReport> {
+        let (Some(workdir), Some(name)) = (archive.parent(), archive.file_stem()) else {
+            anyhow::bail!("Can not extract {}", archive.display());
+        };
+        let (path, workdir, name) = (
+            archive.clone(),
+            workdir.to_path_buf(),
+            name.to_string_lossy().into_owned(),
+        );
+        let report = tokio::task::spawn_blocking(move || {
+            super::file_extractor::extract_file(
+                path,
+                workdir,
+                &name,
+                selection.as_ref(),
+                options.nested_depth,
+                &options.limits,
+            )
+        })
+        .await??;
+
+        if !options.keep {
+            tokio::fs::remove_file(&archive)
+                .await
+                .context("Failed to remove file")?;
+        }
+        Ok(report)
+    }
+
+    /// Name of the archive and extracted directory, or the earlier download to skip.
+    fn choose_name(
+        workdir: &Path,
+        repository: &str,
+        extract: bool,
+        policy: DownloadPolicy,
+    ) -> Result<String, PathBuf> {
+        // The archive, or what was extracted from it when it was not kept
+        let existing = |name: &str| {
+            [
+                Some(workdir.join(format!("{}.zip", name))),
+                extract.then(|| workdir.join(name)),
+            ]
+            .into_iter()
+            .flatten()
+            .find(|path| path.exists())
+        };
+
+        match (existing(repository), policy) {
+            (None, _) | (Some(_), DownloadPolicy::Overwrite) => Ok(repository.to_string()),
+            (Some(path), DownloadPolicy::Skip) => Err(path),
+            (Some(_), DownloadPolicy::Versioned) => Ok((1..)
+                .map(|version| format!("{}-{}", repository, version))
+                .find(|name| existing(name).is_none())
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Streams the archive into `part`, continuing after the bytes already in it.
+    async fn fetch_to_file(
+        client: &DreamhackClient,
+        challenge: &Challenge,
+        part: &Path,
+        progress: &Progress,
+    ) -> anyhow::Result<()> {
+        let offset = tokio::fs::metadata(part)
+            .await
+            .map_or(0, |metadata| metadata.len());
+
+        let mut response = match challenge.download_challenge(client, offset).await {
+            // The partial file does not match the archive anymore, start over
+            Err(DreamhackError::Status {
+                status: StatusCode::RANGE_NOT_SATISFIABLE,
+                ..
+            }) if offset > 0 => challenge.download_challenge(client, 0).await?,
+            result => result?,
+        };
+
+        // Servers without Range support send the whole archive again
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut received = if resumed { offset } else { 0 };
+        let total = response.content_length().map(|length| received + length);
+        progress.set(received, total);
+
+        #[cfg(debug_assertions)]
+        log::info!(
+            "Downloading {} from byte {} of {:?}",
+            part.display(),
+            received,
+            total
+        );
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(part)
+            .await
+            .context("Failed to create file")?;
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("Download interrupted, download again to resume")?
+        {
+            file.write_all(&chunk)
+                .await
+                .context("Failed to write file")?;
+            received += chunk.len() as u64;
+            progress.add(chunk.len() as u64);
+        }
+        file.sync_all().await.context("Failed to write file")?;
+
+        match total {
+            Some(total) if received < total => Err(anyhow::anyhow!(
+                "Download interrupted after {} of {}, download again to resume",
+                format_size(received),
+                format_size(total)
+            )),
+            _ => Ok(()),
+        }
+    }
 }